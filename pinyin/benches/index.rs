@@ -0,0 +1,97 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use piny::PinyinIndex;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Number of strings in the index, about the size of a product catalog.
+const SIZE: u64 = 2_000_000;
+
+/// Common chars to make names of.
+const CHARS: &str = "的一是在不了有和人这中大为上个国我以要他时来用们生到作地于出就分对成会可主发年动\
+    同工也能下过子说产种面而方后多定行学法所民得经十三之进着等部度家电力里如水化高自二理起小物现实加量\
+    都两体制机当使点从业本去把性好应开它合还因由其些然前外天政四日那社义事平形相全表间样与关各重新线内\
+    数正心反你明看原又么利比或但质气第向道命此变条只没结解问意建月公无系军很情者最立代想已通并提直题党\
+    程展五果料象员革位入常文总次品式活设及管特件长求老头基资边流路级少图山统接知较将组见计别她手角期根\
+    论运农指几九区强放决西被干做必战先回则任取据处队南给色光门即保治北造百规热领七海口东导器压志世金增\
+    争济阶油思术极交受联什认六共权收证改清己美再采转更单风切打白教速花带安场身车例真务具万每目至达走积\
+    示议声报斗完类八离华名确才科张信马节话米整空元况今集温传土许步群广石记需段研界拉林律叫且究观越织装";
+
+/// Allocator that counts bytes in use, to report the memory of the index.
+struct CountingAlloc;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+/// Names of 4 to 11 chars, some with a model number, the same for every run.
+fn names() -> impl Iterator<Item = String> {
+    let chars: Vec<char> = CHARS.chars().collect();
+    let mut seed = 42u64;
+    let mut next = move || {
+        seed = seed
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        (seed >> 33) as usize
+    };
+    (0..SIZE).map(move |_| {
+        let len = 4 + next() % 8;
+        let mut name: String = (0..len).map(|_| chars[next() % chars.len()]).collect();
+        if next() % 4 == 0 {
+            name += &format!(" X{}", next() % 1000);
+        }
+        name
+    })
+}
+
+fn index(c: &mut Criterion) {
+    let mut index = PinyinIndex::new();
+    let before = LIVE_BYTES.load(Ordering::Relaxed);
+    for (id, name) in (0..).zip(names()) {
+        index.insert(id, &name);
+    }
+    // requested by the index, without the overhead of the system allocator
+    let bytes = LIVE_BYTES.load(Ordering::Relaxed) - before;
+    println!(
+        "index of {} names: {} MiB, {} bytes per name",
+        SIZE,
+        bytes >> 20,
+        bytes / SIZE as usize
+    );
+
+    let mut group = c.benchmark_group("index/search");
+    for query in ["z", "zg", "zhong", "zhongguo", "zgrmyh", "shengchanjihua"] {
+        group.bench_function(query, |b| b.iter(|| index.search(black_box(query), 10)));
+    }
+    group.finish();
+
+    c.bench_function("index/insert and delete", |b| {
+        let mut id = SIZE;
+        b.iter(|| {
+            index.insert(id, black_box("中国人民银行北京分行"));
+            index.delete(id - 1);
+            id += 1;
+        })
+    });
+}
+
+criterion_group!(benches, index);
+criterion_main!(benches);
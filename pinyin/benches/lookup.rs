@@ -0,0 +1,121 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use nohash_hasher::NoHashHasher;
+use piny::{db, Pinyinizer, ToneRepresentation};
+use std::collections::HashMap;
+use std::hash::BuildHasherDefault;
+
+/// Mostly English text with some Chinese words, like logs and code comments.
+const MIXED: &str = "2023-05-01 12:00:01 INFO 用户登录成功 user_id=42 ip=10.0.0.1 \
+    // 计算订单总价 compute the total price of the order, 含税 including tax\n";
+const CHINESE: &str =
+    "中华人民共和国是工人阶级领导的、以工农联盟为基础的人民民主专政的社会主义国家。";
+
+/// Mostly ASCII text with a few Chinese words.
+const LOG: &str = "2023-05-01 12:00:01 DEBUG request GET /api/v1/orders?page=2&size=20 \
+    headers={accept: application/json, user-agent: curl/8.0} took 12ms status=200 OK\n\
+    2023-05-01 12:00:02 WARN 库存不足 sku=A-1024 warehouse=east retry=3\n";
+
+fn texts() -> [(&'static str, String); 3] {
+    [
+        ("mixed", MIXED.repeat(16)),
+        ("chinese", CHINESE.repeat(16)),
+        ("log", LOG.repeat(16)),
+    ]
+}
+
+/// The lookup structure before the direct table, as a baseline: pages of 256 code points in
+/// a map by page number, each code point has up to 3 readings, all 0 if it has none.
+type Pages = HashMap<u16, [[u16; 3]; 256], BuildHasherDefault<NoHashHasher<u16>>>;
+
+fn pages(db: &db::DB) -> Pages {
+    let mut pages = Pages::with_capacity_and_hasher(1000, BuildHasherDefault::default());
+    for (c, readings) in db.iter() {
+        let page = pages.entry((c as u32 >> 8) as u16).or_insert([[0; 3]; 256]);
+        for (slot, r) in page[c as usize & 0xff].iter_mut().zip(readings.iter()) {
+            *slot = r.into();
+        }
+    }
+    pages
+}
+
+fn lookup(c: &mut Criterion) {
+    let db = db::embedded();
+    let pages = pages(db);
+    for (name, text) in texts() {
+        let mut group = c.benchmark_group(format!("lookup/{}", name));
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_function("direct table", |b| {
+            b.iter(|| {
+                black_box(&text)
+                    .chars()
+                    .filter(|c| db.get(*c).is_some())
+                    .count()
+            })
+        });
+        group.bench_function("hash map of pages", |b| {
+            b.iter(|| {
+                black_box(&text)
+                    .chars()
+                    .filter(|c| {
+                        let code_point = *c as u32;
+                        pages
+                            .get(&((code_point >> 8) as u16))
+                            .is_some_and(|page| page[code_point as u8 as usize] != [0; 3])
+                    })
+                    .count()
+            })
+        });
+        group.finish();
+    }
+}
+
+fn pinyin(c: &mut Criterion) {
+    for (name, text) in texts() {
+        let mut group = c.benchmark_group(format!("pinyin/{}", name));
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_function("pinyin", |b| {
+            b.iter(|| piny::pinyin(black_box(&text), ToneRepresentation::Unicode))
+        });
+        group.bench_function("pinyin into", |b| {
+            let mut buf = String::with_capacity(text.len() * 4);
+            b.iter(|| {
+                buf.clear();
+                piny::pinyin_into(black_box(&text), ToneRepresentation::Unicode, &mut buf).unwrap();
+            })
+        });
+        group.bench_function("first letters", |b| {
+            b.iter(|| piny::first_letters(black_box(&text)))
+        });
+        group.finish();
+    }
+}
+
+/// Conversion with the scan that skips runs of text without reading, and without it: a
+/// reading of `\0` in the user dictionary lowers the threshold of the scan to 0, so that
+/// each char is looked up.
+fn scan(c: &mut Criterion) {
+    let on = Pinyinizer::embedded();
+    let off = Pinyinizer::embedded();
+    off.user_dict()
+        .write()
+        .unwrap()
+        .insert_char('\0', vec!["a".parse().unwrap()]);
+    for (name, text) in texts() {
+        let mut group = c.benchmark_group(format!("scan/{}", name));
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        let mut buf = String::with_capacity(text.len() * 4);
+        for (name, p) in [("on", &on), ("off", &off)] {
+            group.bench_function(name, |b| {
+                b.iter(|| {
+                    buf.clear();
+                    p.pinyin_into(black_box(&text), ToneRepresentation::Unicode, &mut buf)
+                        .unwrap();
+                })
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, lookup, pinyin, scan);
+criterion_main!(benches);
@@ -0,0 +1,139 @@
+//! Compile the embedded `pinyin.txt` to static lookup tables, so that the embedded database
+//! needs no parsing or allocation at runtime.
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::{env, fs, path::Path};
+
+extern crate alloc;
+
+#[path = "src/syllable.rs"]
+#[allow(dead_code)]
+mod syllable;
+
+fn feature(name: &str) -> bool {
+    env::var_os(format!("CARGO_FEATURE_{}", name)).is_some()
+}
+
+/// Chars of GBK code points of `leads` and `trails`.
+#[cfg(feature = "encoding_rs")]
+fn gbk_chars(
+    leads: std::ops::RangeInclusive<u8>,
+    trails: impl Iterator<Item = u8> + Clone,
+) -> Vec<u32> {
+    leads
+        .flat_map(|lead| trails.clone().map(move |trail| [lead, trail]))
+        .filter_map(|bytes| {
+            let (s, had_errors) = encoding_rs::GBK.decode_without_bom_handling(&bytes);
+            let mut chars = s.chars();
+            match (had_errors, chars.next(), chars.next()) {
+                (false, Some(c), None) => Some(c as u32),
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Whether to embed a code point by the selected `coverage-*` features.
+fn coverage() -> impl Fn(u32) -> bool {
+    let sets = ["COMMON", "GB2312", "GBK", "BMP"];
+    let all = feature("COVERAGE_ALL") || !sets.iter().any(|s| feature(&format!("COVERAGE_{}", s)));
+    let bmp = feature("COVERAGE_BMP");
+    #[allow(unused_mut)]
+    let mut chars: HashSet<u32> = HashSet::new();
+    #[cfg(feature = "encoding_rs")]
+    {
+        if feature("COVERAGE_COMMON") {
+            chars.extend(gbk_chars(0xB0..=0xD7, 0xA1..=0xFE));
+        }
+        if feature("COVERAGE_GB2312") {
+            chars.extend(gbk_chars(0xA1..=0xF7, 0xA1..=0xFE));
+        }
+        if feature("COVERAGE_GBK") {
+            chars.extend(gbk_chars(0x81..=0xFE, (0x40..=0x7E).chain(0x80..=0xFE)));
+        }
+    }
+    move |code_point| all || (bmp && code_point < 0x10000) || chars.contains(&code_point)
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=src/syllable.rs");
+    println!("cargo:rerun-if-changed=src/pinyin.txt");
+    if !feature("EMBEDDED_DATA") {
+        return;
+    }
+
+    // same as `db::MAX_READINGS`
+    let max_readings = if feature("POLYPHONE") { 0xff } else { 1 };
+    let covered = coverage();
+
+    let data = fs::read_to_string("src/pinyin.txt").unwrap();
+    let mut directory: Vec<u16> = vec![];
+    let mut entries: Vec<u32> = vec![];
+    let mut pool: Vec<u16> = vec![];
+    // code points of chars with readings dropped, reported by `DB::truncated`
+    let mut truncated: Vec<u32> = vec![];
+    // chars of the same readings share them in the pool
+    let mut offsets: HashMap<Vec<u16>, u32> = HashMap::new();
+    let data = data.strip_prefix('\u{feff}').unwrap_or(&data);
+    for (no, line) in data.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (code_point, readings) = line
+            .split_once(':')
+            .unwrap_or_else(|| panic!("pinyin.txt:{}: missing ':'", no + 1));
+        let code_point = code_point
+            .trim()
+            .strip_prefix("U+")
+            .and_then(|s| u32::from_str_radix(s, 16).ok())
+            .unwrap_or_else(|| panic!("pinyin.txt:{}: bad code point", no + 1));
+        if !covered(code_point) {
+            continue;
+        }
+        let readings: Vec<u16> = readings
+            .split(',')
+            .map(|r| {
+                syllable::parse(r.trim())
+                    .unwrap_or_else(|| panic!("pinyin.txt:{}: unknown syllable '{}'", no + 1, r))
+            })
+            .collect();
+        assert!(
+            readings.len() <= 0xff,
+            "pinyin.txt:{}: too many readings",
+            no + 1
+        );
+        if readings.len() > max_readings {
+            truncated.push(code_point);
+        }
+        let readings = readings[..readings.len().min(max_readings)].to_vec();
+
+        let page = (code_point >> 8) as usize;
+        if directory.len() <= page {
+            directory.resize(page + 1, 0);
+        }
+        if directory[page] == 0 {
+            directory[page] = (entries.len() / 256 + 1) as u16;
+            entries.resize(entries.len() + 256, 0);
+        }
+        let len = readings.len() as u32;
+        let offset = *offsets.entry(readings).or_insert_with_key(|readings| {
+            pool.extend(readings);
+            (pool.len() - readings.len()) as u32
+        });
+        let idx = (directory[page] as usize - 1) * 256 + (code_point & 0xff) as usize;
+        entries[idx] = offset << 8 | len;
+    }
+
+    truncated.sort_unstable();
+    let mut out = String::new();
+    writeln!(
+        out,
+        "DB::from_static(&{:?}, &{:?}, &{:?}, &{:?})",
+        directory, entries, pool, truncated
+    )
+    .unwrap();
+    let path = Path::new(&env::var_os("OUT_DIR").unwrap()).join("embedded_db.rs");
+    fs::write(path, out).unwrap();
+}
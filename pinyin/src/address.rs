@@ -0,0 +1,289 @@
+//! Romanize Chinese place names and addresses.
+use crate::pinyin::{capitalize, word};
+use crate::{Pinyin, Pinyinizer, ToneRepresentation};
+use std::collections::HashMap;
+
+/// Place names read differently from the most common reading of their characters, or that
+/// contain a suffix char, such as `村` of `中关村`.
+#[rustfmt::skip]
+const PLACE_NAMES: &[(&str, &str)] = &[
+    ("六安", "lù ān"), ("丽水", "lí shuǐ"), ("蚌埠", "bèng bù"), ("重庆", "chóng qìng"),
+    ("长沙", "cháng shā"), ("长春", "cháng chūn"), ("长安", "cháng ān"), ("长治", "cháng zhì"),
+    ("厦门", "xià mén"), ("番禺", "pān yú"), ("台州", "tāi zhōu"), ("天台", "tiān tāi"),
+    ("乐亭", "lào tíng"), ("乐清", "yuè qīng"), ("洪洞", "hóng tóng"), ("铅山", "yán shān"),
+    ("东阿", "dōng ē"), ("莘县", "shēn xiàn"), ("枞阳", "zōng yáng"), ("涡阳", "guō yáng"),
+    ("黄陂", "huáng pí"), ("犍为", "qián wéi"), ("筠连", "jūn lián"), ("歙县", "shè xiàn"),
+    ("蔚县", "yù xiàn"), ("临朐", "lín qú"), ("荥阳", "xíng yáng"), ("浚县", "xùn xiàn"),
+    ("单县", "shàn xiàn"), ("曲阜", "qū fù"), ("会稽", "kuài jī"), ("济南", "jǐ nán"),
+    ("济宁", "jǐ níng"), ("东莞", "dōng guǎn"), ("成都", "chéng dū"), ("都江堰", "dū jiāng yàn"),
+    ("朝阳", "cháo yáng"), ("尉氏", "wèi shì"), ("大都", "dà dū"), ("十里堡", "shí lǐ pù"),
+    ("吴堡", "wú bǔ"), ("瑷珲", "ài huī"), ("弋阳", "yì yáng"), ("闽侯", "mǐn hòu"),
+    ("綦江", "qí jiāng"), ("高句丽", "gāo gōu lí"), ("大栅栏", "dà shí làn"), ("兴化", "xīng huà"),
+    ("中关村", "zhōng guān cūn"),
+];
+
+lazy_static::lazy_static! {
+    static ref PLACE_TABLE: HashMap<&'static str, Vec<Pinyin>> = PLACE_NAMES
+        .iter()
+        .map(|(name, reading)| {
+            let reading = reading.split(' ').map(|s| s.parse().unwrap()).collect();
+            (*name, reading)
+        })
+        .collect();
+    static ref MAX_PLACE_NAME_LEN: usize = PLACE_NAMES
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .max()
+        .unwrap();
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Level {
+    /// Administrative division, such as province, city, and district.
+    Division,
+    /// Street or road.
+    Street,
+    /// House number.
+    Number,
+}
+
+struct Suffix {
+    text: &'static str,
+    reading: &'static str,
+    level: Level,
+}
+
+const fn suffix(text: &'static str, reading: &'static str, level: Level) -> Suffix {
+    Suffix {
+        text,
+        reading,
+        level,
+    }
+}
+
+/// Address suffixes and their readings. Longer suffixes must come first.
+const SUFFIXES: &[Suffix] = &[
+    suffix("自治区", "zì zhì qū", Level::Division),
+    suffix("自治州", "zì zhì zhōu", Level::Division),
+    suffix("自治县", "zì zhì xiàn", Level::Division),
+    suffix("街道", "jiē dào", Level::Division),
+    suffix("大道", "dà dào", Level::Street),
+    suffix("大街", "dà jiē", Level::Street),
+    suffix("胡同", "hú tòng", Level::Street),
+    suffix("省", "shěng", Level::Division),
+    suffix("市", "shì", Level::Division),
+    suffix("区", "qū", Level::Division),
+    suffix("县", "xiàn", Level::Division),
+    suffix("旗", "qí", Level::Division),
+    suffix("盟", "méng", Level::Division),
+    suffix("镇", "zhèn", Level::Division),
+    suffix("乡", "xiāng", Level::Division),
+    suffix("村", "cūn", Level::Division),
+    suffix("路", "lù", Level::Street),
+    suffix("街", "jiē", Level::Street),
+    suffix("巷", "xiàng", Level::Street),
+    suffix("弄", "lòng", Level::Street),
+    suffix("号", "hào", Level::Number),
+];
+
+/// A component of an address, such as `朝阳区`.
+struct Component<'a> {
+    name: &'a str,
+    suffix: Option<&'static Suffix>,
+}
+
+impl Component<'_> {
+    fn level(&self) -> Level {
+        self.suffix.map_or(Level::Division, |s| s.level)
+    }
+
+    /// Romanize as `Chaoyang Qu`, house numbers are kept as is.
+    fn romanize(&self, p: &Pinyinizer, tone_repr: ToneRepresentation) -> String {
+        let mut result = if self.name.bytes().all(|b| b.is_ascii_digit()) {
+            self.name.to_string()
+        } else {
+            let full_name = self.suffix.map(|s| format!("{}{}", self.name, s.text));
+            let syllables = match full_name.and_then(|n| PLACE_TABLE.get(n.as_str())) {
+                // place names that include the suffix, such as `单县`
+                Some(reading) => {
+                    let n = self.name.chars().count();
+                    reading[..n].iter().copied().map(Ok).collect()
+                }
+                None => place_readings(p, self.name),
+            };
+            capitalize(&word(syllables.into_iter(), tone_repr))
+        };
+        if let Some(suffix) = self.suffix {
+            let reading = suffix.reading.split(' ').map(|s| Ok(s.parse().unwrap()));
+            if !result.is_empty() {
+                result.push(' ');
+            }
+            result.push_str(&capitalize(&word(reading, tone_repr)));
+        }
+        result
+    }
+}
+
+pub(crate) fn romanize_place(p: &Pinyinizer, name: &str, tone_repr: ToneRepresentation) -> String {
+    capitalize(&word(place_readings(p, name).into_iter(), tone_repr))
+}
+
+pub(crate) fn romanize_address(
+    p: &Pinyinizer,
+    address: &str,
+    tone_repr: ToneRepresentation,
+) -> String {
+    let components = split_address(address);
+    let detail_start = components
+        .iter()
+        .position(|c| c.level() != Level::Division)
+        .unwrap_or(components.len());
+
+    let mut parts = vec![];
+    if detail_start < components.len() {
+        let detail: Vec<String> = components[detail_start..]
+            .iter()
+            .map(|c| c.romanize(p, tone_repr))
+            .collect();
+        parts.push(detail.join(" "));
+    }
+    parts.extend(
+        components[..detail_start]
+            .iter()
+            .rev()
+            .map(|c| c.romanize(p, tone_repr)),
+    );
+    parts.join(", ")
+}
+
+/// Suffix at the start of `s`.
+fn starting_suffix(s: &str) -> Option<&'static Suffix> {
+    SUFFIXES.iter().find(|x| s.starts_with(x.text))
+}
+
+/// Bytes of the longest known place name at the start of `s`, 0 if none.
+fn known_place_len(s: &str) -> usize {
+    PLACE_NAMES
+        .iter()
+        .filter(|(name, _)| s.starts_with(name))
+        .map(|(name, _)| name.len())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Split address into components by suffixes. The last component may have no suffix.
+///
+/// Suffix chars inside a known place name do not split it, such as `村` of `中关村大街`. A
+/// known name that ends with a suffix, such as `单县`, is split before it unless another
+/// suffix follows.
+fn split_address(address: &str) -> Vec<Component<'_>> {
+    let mut result = vec![];
+    let mut rest = address;
+    'outer: while !rest.is_empty() {
+        let known = known_place_len(rest);
+        let known_suffix = SUFFIXES.iter().find(|x| rest[..known].ends_with(x.text));
+        if let Some(suffix) = known_suffix.filter(|_| starting_suffix(&rest[known..]).is_none()) {
+            result.push(Component {
+                name: &rest[..known - suffix.text.len()],
+                suffix: Some(suffix),
+            });
+            rest = &rest[known..];
+            continue;
+        }
+        // name of a component has at least one char
+        let first_len = rest.chars().next().map_or(0, char::len_utf8);
+        let start = first_len.max(known);
+        for (i, _) in rest[start..].char_indices() {
+            let at = start + i;
+            if let Some(suffix) = starting_suffix(&rest[at..]) {
+                result.push(Component {
+                    name: &rest[..at],
+                    suffix: Some(suffix),
+                });
+                rest = &rest[at + suffix.text.len()..];
+                continue 'outer;
+            }
+        }
+        result.push(Component {
+            name: rest,
+            suffix: None,
+        });
+        break;
+    }
+    result
+}
+
+/// Read place names by the dictionary with forward longest match, other chars by the most
+/// common reading.
+fn place_readings(p: &Pinyinizer, s: &str) -> Vec<Result<Pinyin, char>> {
+    let mut result = vec![];
+    let mut rest = s;
+    'outer: while let Some(c) = rest.chars().next() {
+        let ends: Vec<usize> = rest
+            .char_indices()
+            .skip(1)
+            .map(|(i, _)| i)
+            .chain(std::iter::once(rest.len()))
+            .take(*MAX_PLACE_NAME_LEN)
+            .collect();
+        for &end in ends.iter().skip(1).rev() {
+            if let Some(reading) = PLACE_TABLE.get(&rest[..end]) {
+                result.extend(reading.iter().copied().map(Ok));
+                rest = &rest[end..];
+                continue 'outer;
+            }
+        }
+        result.push(p.char_reading(c).ok_or(c));
+        rest = &rest[c.len_utf8()..];
+    }
+    result
+}
+
+#[cfg(test)]
+#[cfg(feature = "embedded-data")]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("六安", "Lù'ān")]
+    #[case("丽水", "Líshuǐ")]
+    #[case("蚌埠", "Bèngbù")]
+    #[case("西安", "Xī'ān")]
+    #[case("朝阳门", "Cháoyángmén")]
+    fn test_romanize_place(#[case] name: &str, #[case] exp: &str) {
+        assert_eq!(
+            crate::romanize_place(name, ToneRepresentation::Unicode),
+            exp
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "北京市朝阳区建国路88号",
+        "Jianguo Lu 88 Hao, Chaoyang Qu, Beijing Shi"
+    )]
+    #[case(
+        "广东省广州市天河区科技园路1号",
+        "Kejiyuan Lu 1 Hao, Tianhe Qu, Guangzhou Shi, Guangdong Sheng"
+    )]
+    #[case("山东省单县", "Shan Xian, Shandong Sheng")]
+    #[case("青岛市市南区", "Shinan Qu, Qingdao Shi")]
+    #[case("安徽省六安市", "Lu'an Shi, Anhui Sheng")]
+    #[case("长安街", "Chang'an Jie")]
+    #[case("中关村大街", "Zhongguancun Dajie")]
+    #[case(
+        "北京市海淀区中关村大街27号",
+        "Zhongguancun Dajie 27 Hao, Haidian Qu, Beijing Shi"
+    )]
+    #[case("海淀区中关村", "Zhongguan Cun, Haidian Qu")]
+    #[case("山东省单县城关镇", "Chengguan Zhen, Shan Xian, Shandong Sheng")]
+    #[case("北京", "Beijing")]
+    #[case("", "")]
+    fn test_romanize_address(#[case] address: &str, #[case] exp: &str) {
+        assert_eq!(
+            crate::romanize_address(address, ToneRepresentation::None),
+            exp
+        );
+    }
+}
@@ -1,121 +1,479 @@
-use crate::Pinyin;
-use nohash_hasher::NoHashHasher;
-use std::{collections::HashMap, hash::BuildHasherDefault};
+use crate::phrase::Phrases;
+use crate::{Pinyin, ReadingCountMismatch};
+use alloc::vec::Vec;
+use core::ops::Deref;
+#[cfg(feature = "std")]
+use std::{io, ops::Range, path::Path};
 
-mod parser;
+#[cfg(feature = "std")]
+mod binary;
+#[cfg(feature = "std")]
+mod export;
+#[cfg(feature = "std")]
+mod layered;
+#[cfg(feature = "std")]
+mod lint;
+#[cfg(feature = "std")]
+pub(crate) mod parser;
+mod reverse;
+#[cfg(feature = "std")]
+mod unihan;
+#[cfg(feature = "std")]
+pub use binary::{BinaryError, FORMAT_VERSION};
+#[cfg(feature = "std")]
+pub use export::ExportFormat;
+#[cfg(feature = "std")]
+pub use layered::{Found, Layer, LayeredDB};
+#[cfg(feature = "std")]
+pub use lint::{diff, is_han, lint, Difference, Lint, LintKind};
+#[cfg(feature = "std")]
+pub use parser::{ParseError, ParseErrorReason, ParseErrors};
+pub use reverse::ReverseIndex;
+#[cfg(feature = "std")]
+pub use unihan::{UnihanField, UnihanPolicy};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
-pub struct Polyphone(u16, u16, u16);
+/// Max number of readings stored for a char, extra readings are dropped on insert.
+#[cfg(feature = "polyphone")]
+pub const MAX_READINGS: usize = 0xff;
+/// Max number of readings stored for a char, extra readings are dropped on insert.
+#[cfg(not(feature = "polyphone"))]
+pub const MAX_READINGS: usize = 1;
 
-impl Polyphone {
-    pub fn new(a: u16, b: u16, c: u16) -> Self {
-        Polyphone(a, b, c)
+/// Readings of a char, the most common reading comes first.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Polyphone<'a>(&'a [u16]);
+
+impl<'a> Polyphone<'a> {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = Pinyin> {
-        let iter = [self.0, self.1, self.2].into_iter();
-        iter.filter(|x| *x != 0).map(|x| x.into())
+    pub fn iter(&self) -> impl Iterator<Item = Pinyin> + 'a {
+        self.0.iter().map(|x| Pinyin::from(*x))
     }
 }
 
 /// Convert first pinyin from polyphone
-impl From<Polyphone> for Pinyin {
+impl From<Polyphone<'_>> for Pinyin {
     fn from(value: Polyphone) -> Self {
-        value.0.into()
+        value.0.first().copied().unwrap_or_default().into()
+    }
+}
+
+/// A table of `DB`, borrowed from static data or bytes of a binary database, or owned.
+#[derive(Clone)]
+enum Table<T: 'static> {
+    Static(&'static [T]),
+    Owned(Vec<T>),
+    /// Range of the bytes, aligned for `T`.
+    #[cfg(feature = "std")]
+    Mapped(binary::Bytes, Range<usize>),
+}
+
+impl<T: bytemuck::Pod> Deref for Table<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        match self {
+            Table::Static(s) => s,
+            Table::Owned(v) => v,
+            #[cfg(feature = "std")]
+            Table::Mapped(bytes, range) => bytemuck::cast_slice(&(**bytes).as_ref()[range.clone()]),
+        }
     }
 }
 
-impl From<Vec<Pinyin>> for Polyphone {
-    fn from(value: Vec<Pinyin>) -> Self {
-        let mut iter = value.into_iter();
-        let first = iter.next().unwrap();
-        let second = iter.next().unwrap_or_default();
-        let third = iter.next().unwrap_or_default();
-        Self(first.into(), second.into(), third.into())
+impl<T: bytemuck::Pod> Table<T> {
+    /// Copy to owned table on first write.
+    fn to_mut(&mut self) -> &mut Vec<T> {
+        if !matches!(self, Table::Owned(_)) {
+            *self = Table::Owned(self.to_vec());
+        }
+        match self {
+            Table::Owned(v) => v,
+            _ => unreachable!(),
+        }
     }
 }
 
+/// How to merge readings of another database, see [`DB::merge`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeMode {
+    /// Add chars and phrases missing in this database, keep existing readings.
+    FillMissing,
+    /// Replace readings by the other database.
+    Override,
+    /// Append readings of the other database that are not in this database. Phrases are
+    /// added as in `FillMissing`.
+    Union,
+}
+
 /// Pinyin database for each chinese character.
 /// Indexed by unicode code point.
+///
+/// Chars are grouped in pages of 256 code points. Readings of all chars are stored in a
+/// shared pool, each code point of a page has an entry of `offset << 8 | len` in the pool,
+/// 0 means no reading. The embedded database is generated by the build script as static
+/// tables.
+#[derive(Clone)]
 pub struct DB {
-    #[cfg(feature = "polyphone")]
-    pages: HashMap<u16, [Polyphone; 256], BuildHasherDefault<NoHashHasher<u16>>>,
-    #[cfg(not(feature = "polyphone"))]
-    pages: HashMap<u16, [Pinyin; 256], BuildHasherDefault<NoHashHasher<u16>>>,
+    /// Index of each page in `entries` plus one, indexed by page number, 0 means empty page.
+    directory: Table<u16>,
+    /// Entries of non-empty pages.
+    entries: Table<u32>,
+    /// Readings in the `u16` representation of `Pinyin`.
+    pool: Table<u16>,
+    /// Code points below it have no reading, so that ASCII and other text before the CJK
+    /// ranges is rejected without touching the tables.
+    low: u32,
+    /// Readings in the pool below it may be shared by chars of the same readings, as in the
+    /// embedded tables, so they are not overwritten when a char is set again.
+    shared: usize,
+    /// Sorted code points of chars whose readings exceeded `MAX_READINGS`, including those
+    /// dropped by the build script from the embedded data.
+    truncated: Table<u32>,
+    phrases: Phrases,
+}
+
+/// First code point of the first non-empty page, `u32::MAX` if all pages are empty.
+const fn low_code_point(directory: &[u16]) -> u32 {
+    let mut page = 0;
+    while page < directory.len() {
+        if directory[page] != 0 {
+            return (page as u32) << 8;
+        }
+        page += 1;
+    }
+    u32::MAX
+}
+
+impl Default for DB {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl DB {
     pub fn new() -> Self {
+        Self::from_static(&[], &[], &[], &[])
+    }
+
+    pub(crate) const fn from_static(
+        directory: &'static [u16],
+        entries: &'static [u32],
+        pool: &'static [u16],
+        truncated: &'static [u32],
+    ) -> Self {
         Self {
-            pages: HashMap::with_capacity_and_hasher(1000, BuildHasherDefault::default()),
+            directory: Table::Static(directory),
+            entries: Table::Static(entries),
+            pool: Table::Static(pool),
+            low: low_code_point(directory),
+            shared: pool.len(),
+            truncated: Table::Static(truncated),
+            phrases: Phrases::new(),
         }
     }
 
-    pub fn load(s: &str) -> Result<Self, nom::error::Error<&str>> {
-        parser::parse_db(s)
+    /// Index of the entry of a code point in `entries`.
+    #[inline]
+    fn entry_index(&self, code_point: u32) -> Option<usize> {
+        if code_point < self.low {
+            return None;
+        }
+        match self.directory.get((code_point >> 8) as usize) {
+            Some(&page) if page != 0 => {
+                Some((page as usize - 1) * 256 + (code_point & 0xff) as usize)
+            }
+            _ => None,
+        }
     }
 
-    #[cfg(feature = "polyphone")]
-    pub fn get(&self, c: char) -> Option<Polyphone> {
-        let code_point = c as u32;
-        let page = (code_point >> 8) as u16;
-        let offset = code_point as u8;
-        self.pages.get(&page).and_then(|page| {
-            let r = page[offset as usize];
-            if r == Polyphone::default() {
-                None
-            } else {
-                Some(r)
+    /// Merge chars and phrases of `other` into this database, such as filling chars of newer
+    /// CJK extensions from Unihan into the database of `pinyin.txt`.
+    pub fn merge(&mut self, other: &DB, mode: MergeMode) {
+        for (c, readings) in other.entries() {
+            let existing = self.readings(c);
+            let merged: Vec<Pinyin> = match mode {
+                MergeMode::FillMissing if !existing.is_empty() => continue,
+                MergeMode::FillMissing | MergeMode::Override => {
+                    readings.iter().map(|r| Pinyin::from(*r)).collect()
+                }
+                MergeMode::Union => {
+                    let mut merged = existing.to_vec();
+                    merged.extend(readings.iter().filter(|r| !existing.contains(r)));
+                    merged.into_iter().map(Pinyin::from).collect()
+                }
+            };
+            self.insert(c, &merged);
+        }
+        for (phrase, readings) in other.phrases() {
+            if mode == MergeMode::Override || self.get_phrase(phrase).is_none() {
+                self.phrases.insert(phrase, readings.to_vec()).unwrap();
             }
-        })
+        }
     }
 
-    #[cfg(not(feature = "polyphone"))]
-    pub fn get(&self, c: char) -> Option<Pinyin> {
-        let code_point = c as u32;
-        let page = (code_point >> 8) as u16;
-        let offset = code_point as u8;
-        self.pages.get(&page).and_then(|page| {
-            let r = page[offset as usize];
-            if r == Pinyin::default() {
-                None
-            } else {
-                Some(r)
+    /// Set readings of a phrase, one reading for each char. Return previous readings if exist.
+    pub fn insert_phrase(
+        &mut self,
+        phrase: &str,
+        readings: Vec<Pinyin>,
+    ) -> Result<Option<Vec<Pinyin>>, ReadingCountMismatch> {
+        self.phrases.insert(phrase, readings)
+    }
+
+    pub fn get_phrase(&self, phrase: &str) -> Option<&[Pinyin]> {
+        self.phrases.get(phrase)
+    }
+
+    /// Iterate over phrases, in the order of their text.
+    pub fn phrases(&self) -> impl Iterator<Item = (&str, &[Pinyin])> {
+        self.phrases.iter()
+    }
+
+    /// Iterate over chars and their readings, in the order of code points.
+    pub fn iter(&self) -> impl Iterator<Item = (char, Polyphone<'_>)> {
+        self.entries()
+            .filter(|(_, readings)| !readings.is_empty())
+            .map(|(c, readings)| (c, Polyphone(readings)))
+    }
+
+    /// Number of chars of the longest phrase.
+    #[cfg(feature = "std")]
+    pub(crate) fn max_phrase_len(&self) -> usize {
+        self.phrases.max_len()
+    }
+
+    /// Lowest code point that starts a char or phrase with readings, `u32::MAX` if empty.
+    #[cfg(feature = "std")]
+    pub(crate) fn lowest_char(&self) -> u32 {
+        let phrase = self.phrases.first_char().map_or(u32::MAX, u32::from);
+        self.low.min(phrase)
+    }
+
+    /// Readings of a char in both polyphone and non-polyphone builds.
+    pub(crate) fn polyphone(&self, c: char) -> Option<Polyphone<'_>> {
+        match self.readings(c) {
+            [] => None,
+            readings => Some(Polyphone(readings)),
+        }
+    }
+
+    /// Chars and their readings, in the order of code points.
+    fn entries(&self) -> impl Iterator<Item = (char, &[u16])> {
+        self.directory
+            .iter()
+            .enumerate()
+            .filter(|(_, page)| **page != 0)
+            .flat_map(move |(page_no, page)| {
+                let start = (*page as usize - 1) * 256;
+                self.entries[start..start + 256]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, entry)| **entry != 0)
+                    .filter_map(move |(i, entry)| {
+                        let offset = (entry >> 8) as usize;
+                        let readings = &self.pool[offset..offset + (entry & 0xff) as usize];
+                        char::from_u32((page_no as u32) << 8 | i as u32).map(|c| (c, readings))
+                    })
+            })
+    }
+
+    /// Readings of a char, empty if not found.
+    fn readings(&self, c: char) -> &[u16] {
+        match self.entry_index(c as u32) {
+            Some(idx) => {
+                let entry = self.entries[idx];
+                let offset = (entry >> 8) as usize;
+                &self.pool[offset..offset + (entry & 0xff) as usize]
             }
-        })
+            None => &[],
+        }
     }
 
     #[cfg(feature = "polyphone")]
-    pub fn insert(&mut self, c: char, polyphone: Polyphone) {
-        let code_point = c as u32;
-        debug_assert!(code_point <= 0xffffff);
-        let page = (code_point >> 8) as u16;
-        let offset = code_point as u8;
-        let page = self
-            .pages
-            .entry(page)
-            .or_insert_with(|| [Polyphone::default(); 256]);
-        debug_assert_eq!(page[offset as usize], Polyphone::default());
-        page[offset as usize] = polyphone;
+    pub fn get(&self, c: char) -> Option<Polyphone<'_>> {
+        self.polyphone(c)
     }
 
     #[cfg(not(feature = "polyphone"))]
-    pub fn insert(&mut self, c: char, polyphone: Polyphone) {
+    pub fn get(&self, c: char) -> Option<Pinyin> {
+        self.polyphone(c).map(Pinyin::from)
+    }
+
+    /// Set readings of a char, replacing existing ones, the most common reading first.
+    /// Readings more than [`MAX_READINGS`] are dropped, return the number of dropped readings.
+    pub fn insert(&mut self, c: char, readings: &[Pinyin]) -> usize {
         let code_point = c as u32;
-        debug_assert!(code_point <= 0xffffff);
-        let page = (code_point >> 8) as u16;
-        let offset = code_point as u8;
-        let page = self
-            .pages
-            .entry(page)
-            .or_insert_with(|| [Pinyin::default(); 256]);
-        debug_assert_eq!(page[offset as usize], Pinyin::default());
-        page[offset as usize] = polyphone.into();
+        let idx = match self.entry_index(code_point) {
+            Some(idx) => idx,
+            None => {
+                let page = (code_point >> 8) as usize;
+                let directory = self.directory.to_mut();
+                if directory.len() <= page {
+                    directory.resize(page + 1, 0);
+                }
+                let entries = self.entries.to_mut();
+                directory[page] = (entries.len() / 256 + 1) as u16;
+                entries.resize(entries.len() + 256, 0);
+                self.low = self.low.min(code_point & !0xff);
+                self.entry_index(code_point).unwrap()
+            }
+        };
+
+        let stored = &readings[..readings.len().min(MAX_READINGS)];
+        let entry = self.entries[idx];
+        let (old_offset, old_len) = ((entry >> 8) as usize, (entry & 0xff) as usize);
+        // the slot of previous readings is reused if the new ones fit
+        let reuse = entry != 0 && old_offset >= self.shared && stored.len() <= old_len;
+        let offset = if reuse { old_offset } else { self.pool.len() };
+        assert!(offset < 1 << 24, "too many readings in pinyin database");
+        let pool = self.pool.to_mut();
+        if reuse {
+            for (slot, p) in pool[offset..].iter_mut().zip(stored) {
+                *slot = u16::from(*p);
+            }
+        } else {
+            pool.extend(stored.iter().map(|p| u16::from(*p)));
+        }
+        self.entries.to_mut()[idx] = (offset as u32) << 8 | stored.len() as u32;
+
+        let dropped = readings.len() - stored.len();
+        match (dropped > 0, self.truncated.binary_search(&code_point)) {
+            (true, Err(i)) => self.truncated.to_mut().insert(i, code_point),
+            (false, Ok(i)) => {
+                self.truncated.to_mut().remove(i);
+            }
+            _ => {}
+        }
+        dropped
+    }
+
+    /// Number of chars that have more readings than [`MAX_READINGS`], their extra readings
+    /// are dropped.
+    pub fn truncated(&self) -> usize {
+        self.truncated.len()
     }
 
     pub fn shrink_to_fit(&mut self) {
-        self.pages.shrink_to_fit();
+        if let Table::Owned(directory) = &mut self.directory {
+            directory.shrink_to_fit();
+        }
+        if let Table::Owned(entries) = &mut self.entries {
+            entries.shrink_to_fit();
+        }
+        if let Table::Owned(pool) = &mut self.pool {
+            pool.shrink_to_fit();
+        }
+    }
+}
+
+/// Loading and saving, they need the `std` feature.
+#[cfg(feature = "std")]
+impl DB {
+    /// Load from text in the syntax of `pinyin.txt` of mozillazg/pinyin-data, such as
+    /// `U+4E2D: zhōng,zhòng  # 中`. Report errors of all bad lines.
+    pub fn load(s: &str) -> Result<Self, ParseErrors> {
+        parser::parse_db(s)
+    }
+
+    /// Load from a file, see [`DB::load`] for the syntax.
+    pub fn from_path(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_reader(std::fs::File::open(path)?)
+    }
+
+    /// Load from a reader, see [`DB::load`] for the syntax.
+    pub fn from_reader(mut reader: impl io::Read) -> io::Result<Self> {
+        let mut s = String::new();
+        reader.read_to_string(&mut s)?;
+        Self::load(&s).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Load a database written by [`DB::write_binary`]. Char tables are used in place
+    /// without copying, `bytes` is kept alive as long as the database, phrases are decoded
+    /// to memory. Format version and checksum are checked.
+    pub fn from_binary(
+        bytes: impl AsRef<[u8]> + Send + Sync + 'static,
+    ) -> Result<Self, BinaryError> {
+        binary::read(std::sync::Arc::new(bytes))
+    }
+
+    /// Memory map a database file written by [`DB::write_binary`], see [`DB::from_binary`].
+    /// The file must not be modified while the database is in use.
+    #[cfg(feature = "mmap")]
+    pub fn map_binary(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = std::fs::File::open(path)?;
+        // SAFETY: the mapped file is documented not to be modified while in use, its content
+        // is validated before use.
+        let mmap = unsafe { memmap2::Mmap::map(&file)? };
+        Ok(Self::from_binary(mmap)?)
+    }
+
+    /// Write in the binary format, see [`DB::from_binary`].
+    pub fn write_binary(&self, w: impl io::Write) -> io::Result<()> {
+        binary::write(self, w)
+    }
+
+    /// Write chars and their readings in `format`, phrases are not included.
+    pub fn export(&self, format: ExportFormat, w: impl io::Write) -> io::Result<()> {
+        export::write(self, format, w)
+    }
+
+    /// Build from `Unihan_Readings.txt` of the Unicode Unihan database, such as
+    /// `U+4E2D<TAB>kMandarin<TAB>zhōng`. Fields other than Mandarin readings are skipped.
+    pub fn from_unihan(s: &str, policy: &UnihanPolicy) -> Result<Self, ParseErrors> {
+        unihan::parse_unihan(s, policy)
+    }
+
+    /// Build from a `Unihan_Readings.txt` file, see [`DB::from_unihan`].
+    pub fn from_unihan_path(path: impl AsRef<Path>, policy: &UnihanPolicy) -> io::Result<Self> {
+        let s = std::fs::read_to_string(path)?;
+        Self::from_unihan(&s, policy).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
     }
+
+    /// Add phrases from text in the syntax of `pinyin.txt` of mozillazg/phrase-pinyin-data,
+    /// such as `中国: zhōng guó`. Nothing is added if there is any error.
+    pub fn load_phrases(&mut self, s: &str) -> Result<(), ParseErrors> {
+        for (phrase, readings) in parser::parse_phrases(s)? {
+            self.phrases.insert(&phrase, readings).unwrap();
+        }
+        Ok(())
+    }
+
+    /// Add phrases from a file, see [`DB::load_phrases`] for the syntax.
+    pub fn load_phrases_file(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let s = std::fs::read_to_string(path)?;
+        self.load_phrases(&s)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Database of the embedded `pinyin.txt`, generated by the build script. Only chars of the
+/// selected `coverage-*` features are embedded.
+#[cfg(feature = "embedded-data")]
+pub(crate) static EMBEDDED: DB = include!(concat!(env!("OUT_DIR"), "/embedded_db.rs"));
+
+/// Database of the embedded `pinyin.txt`, it needs no allocation and is available without
+/// the `std` feature.
+#[cfg(feature = "embedded-data")]
+pub fn embedded() -> &'static DB {
+    &EMBEDDED
+}
+
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+lazy_static::lazy_static! {
+    static ref EMBEDDED_REVERSE: ReverseIndex = ReverseIndex::new(&EMBEDDED);
+}
+
+/// [`ReverseIndex`] of the embedded database, built on first use.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+pub fn embedded_reverse_index() -> &'static ReverseIndex {
+    &EMBEDDED_REVERSE
 }
 
 #[cfg(test)]
@@ -124,48 +482,211 @@ mod tests {
     use crate::pinyin::{py, Finals, Initials, Tones};
 
     #[test]
-    fn to_pinyin() {
-        let polyphone = Polyphone::new(0, 0, 0);
-        let pinyin = Pinyin::from(polyphone);
-        assert_eq!(pinyin, py(Initials::None, Finals::None, Tones::None));
+    fn polyphone_iter() {
+        let zhong = py(Initials::ZH, Finals::Ong, Tones::One);
+        let polyphone = Polyphone(&[zhong.into(), 0x1234]);
+        assert_eq!(polyphone.len(), 2);
+        assert_eq!(polyphone.iter().next(), Some(zhong));
+        assert_eq!(Pinyin::from(polyphone), zhong);
     }
 
     #[test]
-    fn polyphone_iter() {
-        let polyphone = Polyphone::new(0, 0, 0);
-        assert!(polyphone.iter().next().is_none());
+    #[cfg(feature = "polyphone")]
+    fn more_than_three_readings() {
+        let db = DB::load("U+548C: hé,hè,hú,huó,huò,huo  # 和\n").unwrap();
+        let readings: Vec<String> = db
+            .get('和')
+            .unwrap()
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(readings, ["hé", "hè", "hú", "huó", "huò", "huo"]);
+        assert_eq!(db.truncated(), 0);
+    }
+
+    #[test]
+    fn truncate_readings() {
+        let mut db = DB::new();
+        let readings = vec![py(Initials::H, Finals::E, Tones::Two); MAX_READINGS + 2];
+        assert_eq!(db.insert('和', &readings), 2);
+        assert_eq!(db.insert('中', &readings[..1]), 0);
+        assert_eq!(db.truncated(), 1);
+        // counted once per char
+        assert_eq!(db.insert('和', &readings), 2);
+        assert_eq!(db.truncated(), 1);
+        assert_eq!(db.insert('和', &readings[..1]), 0);
+        assert_eq!(db.truncated(), 0);
+    }
+
+    #[test]
+    fn reinsert_reuses_pool() {
+        let zhong = py(Initials::ZH, Finals::Ong, Tones::One);
+        let he = py(Initials::H, Finals::E, Tones::Two);
+        let stored = |r: &[Pinyin]| -> Vec<u16> {
+            r[..r.len().min(MAX_READINGS)]
+                .iter()
+                .map(|p| u16::from(*p))
+                .collect()
+        };
+        let mut db = DB::new();
+        db.insert('中', &[zhong, he]);
+        db.insert('和', &[he]);
+        let len = db.pool.len();
+        db.insert('中', &[he, zhong]);
+        assert_eq!(db.readings('中'), stored(&[he, zhong]));
+        db.insert('中', &[zhong]);
+        assert_eq!(db.pool.len(), len);
+        assert_eq!(db.readings('中'), stored(&[zhong]));
+        // more readings than the slot holds are appended
+        db.insert('和', &[he, zhong]);
+        assert_eq!(db.readings('和'), stored(&[he, zhong]));
+        assert_eq!(db.readings('中'), stored(&[zhong]));
+    }
+
+    #[test]
+    fn keep_shared_static_readings() {
+        let zhong = py(Initials::ZH, Finals::Ong, Tones::One);
+        let he = py(Initials::H, Finals::E, Tones::Two);
+        // `中` and `丮` share the reading in the pool
+        let mut directory = vec![0; 0x4F];
+        directory[0x4E] = 1;
+        let mut entries = vec![0; 256];
+        entries[0x2D] = 1;
+        entries[0x2E] = 1;
+        let pool = vec![u16::from(zhong)];
+        let mut db = DB::from_static(
+            Vec::leak(directory),
+            Vec::leak(entries),
+            Vec::leak(pool),
+            &[],
+        );
+        db.insert('中', &[he]);
+        assert_eq!(db.readings('中'), [u16::from(he)]);
+        assert_eq!(db.readings('丮'), [u16::from(zhong)]);
+    }
+
+    #[test]
+    fn load_from_reader_and_path() {
+        let data = "U+4E2D: zhōng,zhòng  # 中\n";
+        let db = DB::from_reader(data.as_bytes()).unwrap();
+        assert!(db.get('中').is_some());
+
+        let path = std::env::temp_dir().join(format!("piny-db-{}.txt", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        let db = DB::from_path(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(db.unwrap().get('中').is_some());
+
+        let err = DB::from_path("/non-exist/pinyin.txt").err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
     }
 
     #[test]
-    fn vec_to_polyphone() {
-        let py1 = py(Initials::None, Finals::A, Tones::None);
-        let v = vec![py1];
-        let p = Polyphone::from(v);
-        assert_eq!((p.0, p.1, p.2), (py1.into(), 0, 0));
+    fn upstream_syntax() {
+        let data = "\u{feff}# version: 0.14.0\r\n\r\nU+5463: ḿ,m\u{300}  # 呣\r\n\
+                    U+54FC: hēng,hng  # 哼\r\nU+6B38: āi,ê\u{304},ế,ê\u{30c},ề  # 欸\r\n";
+        let db = DB::load(data).unwrap();
+        let readings = |c| Pinyin::from(db.get(c).unwrap()).to_string();
+        assert_eq!(readings('呣'), "ḿ");
+        assert_eq!(readings('哼'), "hēng");
+        assert_eq!(readings('欸'), "āi");
+        #[cfg(feature = "polyphone")]
+        assert_eq!(db.get('欸').unwrap().len(), 5);
+    }
+
+    #[test]
+    fn load_phrases() {
+        let mut db = DB::new();
+        let data = "# comment\r\n一一对应: yī yī duì yìng\r\n\r\n中国: zhōng guó  # c\n";
+        db.load_phrases(data).unwrap();
+        let readings: Vec<String> = db
+            .get_phrase("中国")
+            .unwrap()
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(readings, ["zhōng", "guó"]);
+        assert_eq!(db.phrases().count(), 2);
+        assert_eq!(
+            db.phrases.match_prefix("中国人").map(|(len, _)| len),
+            Some("中国".len())
+        );
+
+        let errors = db
+            .load_phrases("中国: zhōng\n银行: yín xyz\n")
+            .err()
+            .unwrap();
+        assert_eq!(
+            errors.to_string(),
+            "line 1, column 1: phrase '中国' has 2 chars but 1 readings\n\
+             line 2, column 9: unknown syllable 'xyz'"
+        );
+        assert_eq!(db.phrases().count(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-data")]
+    fn embedded_same_as_parsed() {
+        let parsed = DB::load(include_str!("pinyin.txt")).unwrap();
+        for (c, readings) in EMBEDDED.iter() {
+            assert_eq!(parsed.polyphone(c), Some(readings), "{}", c);
+        }
+        // `coverage-*` features other than `coverage-all` embed a subset
+        let subset = cfg!(all(
+            not(feature = "coverage-all"),
+            any(
+                feature = "coverage-common",
+                feature = "coverage-gb2312",
+                feature = "coverage-gbk",
+                feature = "coverage-bmp"
+            )
+        ));
+        if !subset {
+            assert_eq!(EMBEDDED.iter().count(), parsed.iter().count());
+            assert_eq!(EMBEDDED.truncated(), parsed.truncated());
+        }
     }
 
     #[test]
     #[cfg(feature = "polyphone")]
     fn db_put_get() {
         let mut db = DB::new();
-        let polyphone = Polyphone::new(1, 0, 0);
-        db.insert('a', polyphone);
-        assert_eq!(db.get('a'), Some(polyphone));
+        let readings = [py(Initials::None, Finals::A, Tones::One)];
+        db.insert('a', &readings);
+        assert_eq!(db.get('a'), Some(Polyphone(&[readings[0].into()])));
 
-        db.insert('汉', polyphone);
-        assert_eq!(db.get('汉'), Some(polyphone));
+        db.insert('汉', &readings);
+        assert_eq!(db.get('汉').unwrap().iter().collect::<Vec<_>>(), readings);
         assert_eq!(db.get('b'), None);
+
+        let replaced = [py(Initials::H, Finals::AN, Tones::Four)];
+        db.insert('汉', &replaced);
+        assert_eq!(db.get('汉').unwrap().iter().collect::<Vec<_>>(), replaced);
+    }
+
+    #[test]
+    fn reject_below_first_page() {
+        let mut db = DB::new();
+        assert_eq!(db.low, u32::MAX);
+        db.insert('汉', &["hàn".parse().unwrap()]);
+        assert_eq!(db.low, 0x6C00);
+        assert!(db.get('a').is_none());
+        db.insert('a', &["a".parse().unwrap()]);
+        assert_eq!(db.low, 0);
+        assert!(db.get('a').is_some() && db.get('汉').is_some());
+        #[cfg(feature = "embedded-data")]
+        assert!(EMBEDDED.low >= 0x3000);
     }
 
     #[test]
     #[cfg(not(feature = "polyphone"))]
     fn db_put_get() {
         let mut db = DB::new();
-        let pinyin = Pinyin::new(1, 0, 0);
-        db.insert('a', py(Initials::None, Finals::A, Tones::None));
+        let pinyin = py(Initials::None, Finals::A, Tones::None);
+        db.insert('a', &[pinyin]);
         assert_eq!(db.get('a'), Some(pinyin));
 
-        db.insert('汉', pinyin);
+        db.insert('汉', &[pinyin]);
         assert_eq!(db.get('汉'), Some(pinyin));
         assert_eq!(db.get('b'), None);
     }
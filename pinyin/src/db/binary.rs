@@ -0,0 +1,321 @@
+//! Binary format of the pinyin database, its char tables can be used in place without
+//! copying, such as from a memory mapped file.
+//!
+//! All numbers are little endian. The file starts with a header of 32 bytes:
+//!
+//! | offset | size | content                                        |
+//! |--------|------|------------------------------------------------|
+//! | 0      | 8    | magic `PINYINDB`                               |
+//! | 8      | 4    | format version                                 |
+//! | 12     | 4    | number of `u16` in directory                   |
+//! | 16     | 4    | number of `u32` in entries                     |
+//! | 20     | 4    | number of `u16` in reading pool                |
+//! | 24     | 4    | number of bytes of phrases                     |
+//! | 28     | 4    | CRC32 of all bytes after the header            |
+//!
+//! Followed by the directory, entries and reading pool tables of `DB`, each table is padded
+//! to 4 bytes. Phrases come last, each phrase is its length in bytes as `u16`, its UTF-8
+//! text, then its readings as `u16` in the same order as chars.
+use super::{Table, DB};
+use crate::pinyin::is_valid_pinyin;
+use crate::Pinyin;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
+
+const MAGIC: &[u8; 8] = b"PINYINDB";
+/// Current format version, readers reject other versions.
+pub const FORMAT_VERSION: u32 = 1;
+const HEADER_LEN: usize = 32;
+
+/// Error of loading a binary database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryError {
+    /// Not a binary pinyin database.
+    BadMagic,
+    /// Written by an incompatible version of the format.
+    UnsupportedVersion(u32),
+    /// Data is shorter than its header declares.
+    Truncated,
+    ChecksumMismatch,
+    /// Checksum is good but the content is inconsistent.
+    Corrupted(&'static str),
+}
+
+impl Display for BinaryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BinaryError::BadMagic => write!(f, "not a binary pinyin database"),
+            BinaryError::UnsupportedVersion(v) => write!(
+                f,
+                "unsupported binary pinyin database version {}, expect {}",
+                v, FORMAT_VERSION
+            ),
+            BinaryError::Truncated => write!(f, "binary pinyin database is truncated"),
+            BinaryError::ChecksumMismatch => write!(f, "binary pinyin database checksum mismatch"),
+            BinaryError::Corrupted(reason) => {
+                write!(f, "binary pinyin database is corrupted: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+impl From<BinaryError> for io::Error {
+    fn from(e: BinaryError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Bytes of a binary database shared by its tables.
+pub(crate) type Bytes = Arc<dyn AsRef<[u8]> + Send + Sync>;
+
+fn padding(len: usize) -> usize {
+    (4 - len % 4) % 4
+}
+
+fn read_u32(data: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+}
+
+pub(crate) fn write(db: &DB, mut w: impl io::Write) -> io::Result<()> {
+    let mut body = vec![];
+    for d in db.directory.iter() {
+        body.extend_from_slice(&d.to_le_bytes());
+    }
+    body.resize(body.len() + padding(body.len()), 0);
+    for e in db.entries.iter() {
+        body.extend_from_slice(&e.to_le_bytes());
+    }
+    for r in db.pool.iter() {
+        body.extend_from_slice(&r.to_le_bytes());
+    }
+    body.resize(body.len() + padding(body.len()), 0);
+    let phrases_start = body.len();
+    for (phrase, readings) in db.phrases() {
+        let len = u16::try_from(phrase.len()).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("phrase of {} bytes is too long", phrase.len()),
+            )
+        })?;
+        body.extend_from_slice(&len.to_le_bytes());
+        body.extend_from_slice(phrase.as_bytes());
+        for r in readings {
+            body.extend_from_slice(&u16::from(*r).to_le_bytes());
+        }
+    }
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(MAGIC);
+    header.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    for len in [
+        db.directory.len(),
+        db.entries.len(),
+        db.pool.len(),
+        body.len() - phrases_start,
+    ] {
+        header.extend_from_slice(&(len as u32).to_le_bytes());
+    }
+    header.extend_from_slice(&crc32fast::hash(&body).to_le_bytes());
+    w.write_all(&header)?;
+    w.write_all(&body)
+}
+
+/// Load a binary database, its char tables refer to `bytes` if possible.
+pub(crate) fn read(bytes: Bytes) -> Result<DB, BinaryError> {
+    let data = (*bytes).as_ref();
+    if data.len() >= MAGIC.len() && &data[..MAGIC.len()] != MAGIC {
+        return Err(BinaryError::BadMagic);
+    }
+    if data.len() < HEADER_LEN {
+        return Err(BinaryError::Truncated);
+    }
+    let version = read_u32(data, 8);
+    if version != FORMAT_VERSION {
+        return Err(BinaryError::UnsupportedVersion(version));
+    }
+    let directory_len = read_u32(data, 12) as usize * 2;
+    let entries_len = read_u32(data, 16) as usize * 4;
+    let pool_len = read_u32(data, 20) as usize * 2;
+    let phrases_len = read_u32(data, 24) as usize;
+
+    let directory = HEADER_LEN..HEADER_LEN + directory_len;
+    let entries = directory.end + padding(directory_len)..;
+    let entries = entries.start..entries.start + entries_len;
+    let pool = entries.end..entries.end + pool_len;
+    let phrases = pool.end + padding(pool.end)..;
+    let phrases = phrases.start..phrases.start + phrases_len;
+    if data.len() != phrases.end {
+        return Err(BinaryError::Truncated);
+    }
+    if crc32fast::hash(&data[HEADER_LEN..]) != read_u32(data, 28) {
+        return Err(BinaryError::ChecksumMismatch);
+    }
+
+    let mut db = DB {
+        directory: table(&bytes, directory),
+        entries: table(&bytes, entries),
+        pool: table(&bytes, pool),
+        ..DB::new()
+    };
+    db.low = super::low_code_point(&db.directory);
+    db.shared = db.pool.len();
+    validate(&db)?;
+    for (phrase, readings) in read_phrases(&data[phrases])? {
+        db.insert_phrase(phrase, readings)
+            .map_err(|_| BinaryError::Corrupted("phrase reading count"))?;
+    }
+    Ok(db)
+}
+
+/// Table of `range` of `bytes`, used in place if the platform has the same byte order and
+/// the table is aligned, otherwise copied.
+fn table<T: bytemuck::Pod>(bytes: &Bytes, range: Range<usize>) -> Table<T> {
+    let data = &(**bytes).as_ref()[range.clone()];
+    if cfg!(target_endian = "little") && bytemuck::try_cast_slice::<u8, T>(data).is_ok() {
+        Table::Mapped(bytes.clone(), range)
+    } else {
+        let mut v: Vec<T> = vec![T::zeroed(); data.len() / std::mem::size_of::<T>()];
+        let dst = bytemuck::cast_slice_mut::<T, u8>(&mut v);
+        dst.copy_from_slice(data);
+        if cfg!(target_endian = "big") {
+            // swap each item from little endian
+            dst.chunks_mut(std::mem::size_of::<T>())
+                .for_each(<[u8]>::reverse);
+        }
+        Table::Owned(v)
+    }
+}
+
+/// Check tables refer to valid readings, so that lookups never panic.
+fn validate(db: &DB) -> Result<(), BinaryError> {
+    if !db.entries.len().is_multiple_of(256) {
+        return Err(BinaryError::Corrupted("entries not in pages"));
+    }
+    let pages = db.entries.len() / 256;
+    if db.directory.iter().any(|page| *page as usize > pages) {
+        return Err(BinaryError::Corrupted("page out of range"));
+    }
+    let in_pool = |entry: &u32| (entry >> 8) as usize + (entry & 0xff) as usize <= db.pool.len();
+    if !db.entries.iter().all(in_pool) {
+        return Err(BinaryError::Corrupted("reading out of range"));
+    }
+    if !db.pool.iter().all(|r| is_valid_pinyin(*r)) {
+        return Err(BinaryError::Corrupted("invalid reading"));
+    }
+    Ok(())
+}
+
+fn read_phrases(mut data: &[u8]) -> Result<Vec<(&str, Vec<Pinyin>)>, BinaryError> {
+    let mut result = vec![];
+    while !data.is_empty() {
+        let len = data
+            .get(..2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+            .ok_or(BinaryError::Corrupted("phrase length"))?;
+        let phrase = data
+            .get(2..2 + len)
+            .and_then(|b| std::str::from_utf8(b).ok())
+            .ok_or(BinaryError::Corrupted("phrase text"))?;
+        let readings_len = phrase.chars().count() * 2;
+        let readings = data
+            .get(2 + len..2 + len + readings_len)
+            .ok_or(BinaryError::Corrupted("phrase readings"))?
+            .chunks(2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+            .map(|r| is_valid_pinyin(r).then(|| Pinyin::from(r)))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(BinaryError::Corrupted("invalid reading"))?;
+        result.push((phrase, readings));
+        data = &data[2 + len + readings_len..];
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> DB {
+        let mut db = DB::load("U+4E2D: zhōng,zhòng\nU+548C: hé,hè,huó\nU+20BBE: ǹ\n").unwrap();
+        db.load_phrases("中国: zhōng guó\n").unwrap();
+        db
+    }
+
+    fn to_binary(db: &DB) -> Vec<u8> {
+        let mut v = vec![];
+        db.write_binary(&mut v).unwrap();
+        v
+    }
+
+    #[test]
+    fn round_trip() {
+        let db = sample();
+        let loaded = DB::from_binary(to_binary(&db)).unwrap();
+        for c in ['中', '和', '\u{20BBE}', '国', 'a'] {
+            assert_eq!(loaded.get(c), db.get(c));
+        }
+        assert_eq!(loaded.get_phrase("中国"), db.get_phrase("中国"));
+        assert!(matches!(loaded.pool, Table::Mapped(..)));
+
+        // mapped tables are copied on write
+        let mut loaded = loaded;
+        loaded.insert('国', &["guó".parse().unwrap()]);
+        assert!(loaded.get('国').is_some());
+        assert_eq!(loaded.get('中'), db.get('中'));
+    }
+
+    #[test]
+    fn reject_long_phrase() {
+        let mut db = sample();
+        let zhong: Pinyin = "zhōng".parse().unwrap();
+        // 3 bytes of each char, more than `u16::MAX` bytes
+        db.insert_phrase(&"中".repeat(22_000), vec![zhong; 22_000])
+            .unwrap();
+        let err = db.write_binary(vec![]).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn static_bytes() {
+        let data: &'static [u8] = Box::leak(to_binary(&sample()).into_boxed_slice());
+        assert!(DB::from_binary(data).unwrap().get('中').is_some());
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn map_file() {
+        let path = std::env::temp_dir().join(format!("piny-db-{}.bin", std::process::id()));
+        sample()
+            .write_binary(std::fs::File::create(&path).unwrap())
+            .unwrap();
+        let db = DB::map_binary(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(db.unwrap().get('中'), sample().get('中'));
+    }
+
+    #[test]
+    fn bad_data() {
+        let data = to_binary(&sample());
+        let load = |data: Vec<u8>| DB::from_binary(data).err().unwrap();
+
+        assert_eq!(load(b"PINYIN".to_vec()), BinaryError::Truncated);
+        assert_eq!(load(b"NOTPINYIN".to_vec()), BinaryError::BadMagic);
+
+        let mut v = data.clone();
+        v[8] = 2;
+        assert_eq!(load(v), BinaryError::UnsupportedVersion(2));
+
+        assert_eq!(
+            load(data[..data.len() - 1].to_vec()),
+            BinaryError::Truncated
+        );
+
+        let mut v = data.clone();
+        *v.last_mut().unwrap() ^= 1;
+        assert_eq!(load(v), BinaryError::ChecksumMismatch);
+    }
+}
@@ -0,0 +1,161 @@
+//! Export chars and their readings of a database to other formats.
+use super::{Polyphone, DB};
+use std::fmt::Write as _;
+use std::io;
+
+/// Format of [`DB::export`], readings are written with unicode tone marks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// An array of objects, such as
+    /// `{"char": "中", "code_point": "U+4E2D", "readings": ["zhōng", "zhòng"]}`.
+    Json,
+    /// Columns of `char,code_point,readings` with a header line, readings are separated by
+    /// space, such as `中,U+4E2D,zhōng zhòng`.
+    Csv,
+    /// The syntax of `pinyin.txt`, such as `U+4E2D: zhōng,zhòng  # 中`, it can be loaded by
+    /// [`DB::load`] without loss.
+    Text,
+}
+
+fn readings(readings: Polyphone, sep: &str) -> String {
+    let readings: Vec<String> = readings.iter().map(|p| p.to_string()).collect();
+    readings.join(sep)
+}
+
+fn json_str(s: &str) -> String {
+    let mut r = String::with_capacity(s.len() + 2);
+    r.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => r.push_str("\\\""),
+            '\\' => r.push_str("\\\\"),
+            c if c.is_control() => write!(r, "\\u{:04x}", c as u32).unwrap(),
+            c => r.push(c),
+        }
+    }
+    r.push('"');
+    r
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+pub(crate) fn write(db: &DB, format: ExportFormat, mut w: impl io::Write) -> io::Result<()> {
+    match format {
+        ExportFormat::Json => {
+            write!(w, "[")?;
+            for (i, (c, r)) in db.iter().enumerate() {
+                let r: Vec<String> = r.iter().map(|p| json_str(&p.to_string())).collect();
+                write!(
+                    w,
+                    "{}\n  {{\"char\": {}, \"code_point\": \"U+{:04X}\", \"readings\": [{}]}}",
+                    if i == 0 { "" } else { "," },
+                    json_str(c.encode_utf8(&mut [0; 4])),
+                    c as u32,
+                    r.join(", ")
+                )?;
+            }
+            writeln!(w, "\n]")
+        }
+        ExportFormat::Csv => {
+            writeln!(w, "char,code_point,readings")?;
+            for (c, r) in db.iter() {
+                writeln!(
+                    w,
+                    "{},U+{:04X},{}",
+                    csv_field(c.encode_utf8(&mut [0; 4])),
+                    c as u32,
+                    readings(r, " ")
+                )?;
+            }
+            Ok(())
+        }
+        ExportFormat::Text => {
+            for (c, r) in db.iter() {
+                write!(w, "U+{:04X}: {}", c as u32, readings(r, ","))?;
+                // a control char in comment may break the line
+                if c.is_control() {
+                    writeln!(w)?;
+                } else {
+                    writeln!(w, "  # {}", c)?;
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::diff;
+
+    fn export(db: &DB, format: ExportFormat) -> String {
+        let mut v = vec![];
+        db.export(format, &mut v).unwrap();
+        String::from_utf8(v).unwrap()
+    }
+
+    fn sample() -> DB {
+        DB::load("U+4E2D: zhōng,zhòng\nU+0022: yǐn\nU+002C: dòu\nU+000A: ǹg\n").unwrap()
+    }
+
+    #[test]
+    fn iter() {
+        let db = sample();
+        let chars: Vec<_> = db.iter().map(|(c, _)| c).collect();
+        assert_eq!(chars, ['\n', '"', ',', '中']);
+        assert_eq!(db.iter().last().unwrap().1, db.polyphone('中').unwrap());
+        assert_eq!(DB::new().iter().count(), 0);
+    }
+
+    #[test]
+    #[cfg(feature = "polyphone")]
+    fn json() {
+        assert_eq!(
+            export(&sample(), ExportFormat::Json),
+            "[\n  \
+            {\"char\": \"\\u000a\", \"code_point\": \"U+000A\", \"readings\": [\"ǹg\"]},\n  \
+            {\"char\": \"\\\"\", \"code_point\": \"U+0022\", \"readings\": [\"yǐn\"]},\n  \
+            {\"char\": \",\", \"code_point\": \"U+002C\", \"readings\": [\"dòu\"]},\n  \
+            {\"char\": \"中\", \"code_point\": \"U+4E2D\", \"readings\": [\"zhōng\", \"zhòng\"]}\n\
+            ]\n"
+        );
+        assert_eq!(export(&DB::new(), ExportFormat::Json), "[\n]\n");
+    }
+
+    #[test]
+    #[cfg(feature = "polyphone")]
+    fn csv() {
+        assert_eq!(
+            export(&sample(), ExportFormat::Csv),
+            "char,code_point,readings\n\
+            \"\n\",U+000A,ǹg\n\
+            \"\"\"\",U+0022,yǐn\n\
+            \",\",U+002C,dòu\n\
+            中,U+4E2D,zhōng zhòng\n"
+        );
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let db = sample();
+        let text = export(&db, ExportFormat::Text);
+        assert!(text.contains("U+0022: yǐn  # \"\n"));
+        assert!(diff(&db, &DB::load(&text).unwrap()).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "embedded-data")]
+    fn embedded_round_trip() {
+        let db = &crate::db::EMBEDDED;
+        let loaded = DB::load(&export(db, ExportFormat::Text)).unwrap();
+        assert!(diff(db, &loaded).is_empty());
+        assert_eq!(loaded.iter().count(), db.iter().count());
+    }
+}
@@ -0,0 +1,247 @@
+//! Databases stacked in layers, such as a base dataset, a company overlay and a per-tenant
+//! overlay. Upper layers take precedence over lower layers.
+use super::{Polyphone, DB};
+use crate::phrase::longest_prefix;
+use crate::{Pinyin, ReadingCountMismatch};
+use std::collections::HashSet;
+
+/// A layer of [`LayeredDB`], it adds or replaces readings by its own database, and deletes
+/// chars and phrases of lower layers.
+#[derive(Clone)]
+pub struct Layer {
+    name: String,
+    db: DB,
+    deleted_chars: HashSet<char>,
+    deleted_phrases: HashSet<String>,
+}
+
+impl Layer {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self::with_db(name, DB::new())
+    }
+
+    pub fn with_db(name: impl Into<String>, db: DB) -> Self {
+        Self {
+            name: name.into(),
+            db,
+            deleted_chars: HashSet::new(),
+            deleted_phrases: HashSet::new(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+
+    /// Add or replace readings of a char.
+    pub fn set_char(&mut self, c: char, readings: &[Pinyin]) {
+        self.deleted_chars.remove(&c);
+        self.db.insert(c, readings);
+    }
+
+    /// Hide the char of lower layers and this layer.
+    pub fn delete_char(&mut self, c: char) {
+        self.deleted_chars.insert(c);
+    }
+
+    /// Add or replace readings of a phrase, one reading for each char.
+    pub fn set_phrase(
+        &mut self,
+        phrase: &str,
+        readings: Vec<Pinyin>,
+    ) -> Result<(), ReadingCountMismatch> {
+        self.db.insert_phrase(phrase, readings)?;
+        self.deleted_phrases.remove(phrase);
+        Ok(())
+    }
+
+    /// Hide the phrase of lower layers and this layer.
+    pub fn delete_phrase(&mut self, phrase: &str) {
+        self.deleted_phrases.insert(phrase.to_string());
+    }
+
+    /// `Some(None)` if the char is deleted by this layer, `None` if this layer knows nothing
+    /// about it.
+    fn get(&self, c: char) -> Option<Option<Polyphone<'_>>> {
+        if !self.deleted_chars.is_empty() && self.deleted_chars.contains(&c) {
+            return Some(None);
+        }
+        self.db.polyphone(c).map(Some)
+    }
+
+    fn get_phrase(&self, phrase: &str) -> Option<Option<&[Pinyin]>> {
+        if !self.deleted_phrases.is_empty() && self.deleted_phrases.contains(phrase) {
+            return Some(None);
+        }
+        self.db.get_phrase(phrase).map(Some)
+    }
+}
+
+/// Value of a lookup and the name of the layer that supplied it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Found<'a, T> {
+    pub layer: &'a str,
+    pub value: T,
+}
+
+/// Databases consulted from the top layer to the bottom, the first layer that has or deletes
+/// a char or phrase answers the lookup.
+#[derive(Clone, Default)]
+pub struct LayeredDB {
+    /// From bottom to top.
+    layers: Vec<Layer>,
+}
+
+impl LayeredDB {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a layer on top of existing layers.
+    pub fn push(&mut self, layer: Layer) {
+        self.layers.push(layer);
+    }
+
+    /// Layers from bottom to top.
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn layer_mut(&mut self, name: &str) -> Option<&mut Layer> {
+        self.layers.iter_mut().find(|l| l.name == name)
+    }
+
+    /// Readings of a char, `None` if no layer has it or the top layer that knows it deleted
+    /// it.
+    pub fn get(&self, c: char) -> Option<Found<'_, Polyphone<'_>>> {
+        self.layers.iter().rev().find_map(|l| {
+            l.get(c).map(|r| {
+                r.map(|value| Found {
+                    layer: &l.name,
+                    value,
+                })
+            })
+        })?
+    }
+
+    /// Readings of a phrase, see [`LayeredDB::get`].
+    pub fn get_phrase(&self, phrase: &str) -> Option<Found<'_, &[Pinyin]>> {
+        self.layers.iter().rev().find_map(|l| {
+            l.get_phrase(phrase).map(|r| {
+                r.map(|value| Found {
+                    layer: &l.name,
+                    value,
+                })
+            })
+        })?
+    }
+
+    /// Lowest code point that starts a char or phrase of all layers, `u32::MAX` if empty.
+    pub(crate) fn lowest_char(&self) -> u32 {
+        self.layers
+            .iter()
+            .map(|l| l.db.lowest_char())
+            .min()
+            .unwrap_or(u32::MAX)
+    }
+
+    /// Find the longest phrase of all layers at the start of `s`, return the phrase length
+    /// in bytes and its readings.
+    pub(crate) fn match_phrase(&self, s: &str) -> Option<(usize, &[Pinyin])> {
+        let max_len = self.layers.iter().map(|l| l.db.max_phrase_len()).max()?;
+        longest_prefix(s, max_len, |p| self.get_phrase(p).map(|f| f.value))
+    }
+}
+
+impl From<DB> for LayeredDB {
+    /// Single layer named `base`.
+    fn from(db: DB) -> Self {
+        Self {
+            layers: vec![Layer::with_db("base", db)],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readings(s: &str) -> Vec<Pinyin> {
+        s.split(' ').map(|s| s.parse().unwrap()).collect()
+    }
+
+    fn layered() -> LayeredDB {
+        let mut base = DB::load("U+4E2D: zhōng,zhòng\nU+94F6: yín\nU+884C: xíng,háng\n").unwrap();
+        base.load_phrases("银行: yín háng\n").unwrap();
+        let mut db = LayeredDB::from(base);
+        db.push(Layer::new("company"));
+        db.push(Layer::new("tenant"));
+        db
+    }
+
+    #[test]
+    fn lookup_order() {
+        let mut db = layered();
+        let found = db.get('中').unwrap();
+        assert_eq!(
+            (found.layer, found.value.iter().next()),
+            ("base", readings("zhōng").pop())
+        );
+        assert!(db.get('国').is_none());
+
+        db.layer_mut("company")
+            .unwrap()
+            .set_char('中', &readings("zhòng"));
+        db.layer_mut("tenant")
+            .unwrap()
+            .set_char('国', &readings("guó"));
+        let found = db.get('中').unwrap();
+        assert_eq!(
+            (found.layer, found.value.iter().next()),
+            ("company", readings("zhòng").pop())
+        );
+        assert_eq!(db.get('国').unwrap().layer, "tenant");
+    }
+
+    #[test]
+    fn delete() {
+        let mut db = layered();
+        db.layer_mut("company")
+            .unwrap()
+            .set_char('银', &readings("yin"));
+        db.layer_mut("tenant").unwrap().delete_char('银');
+        assert!(db.get('银').is_none());
+
+        // a deleted char can be added back by upper layer
+        db.push(Layer::new("user"));
+        db.layer_mut("user")
+            .unwrap()
+            .set_char('银', &readings("yín"));
+        assert_eq!(db.get('银').unwrap().layer, "user");
+
+        db.layer_mut("user").unwrap().delete_phrase("银行");
+        assert!(db.get_phrase("银行").is_none());
+        assert_eq!(db.match_phrase("银行卡"), None);
+    }
+
+    #[test]
+    fn phrases() {
+        let mut db = layered();
+        assert_eq!(
+            db.get_phrase("银行"),
+            Some(Found {
+                layer: "base",
+                value: readings("yín háng").as_slice()
+            })
+        );
+        let tenant = db.layer_mut("tenant").unwrap();
+        tenant.set_phrase("中行", readings("zhōng háng")).unwrap();
+        assert!(tenant.set_phrase("中行", readings("zhōng")).is_err());
+        assert_eq!(db.match_phrase("中行行长").unwrap().0, "中行".len());
+        assert_eq!(db.get_phrase("中行").unwrap().layer, "tenant");
+    }
+}
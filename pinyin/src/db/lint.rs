@@ -0,0 +1,306 @@
+//! Check pinyin data files for mistakes that loading tolerates or reports one at a time.
+use super::parser::{column, key_value, parse_entries, ParseErrorReason};
+use super::{Polyphone, DB, MAX_READINGS};
+use crate::syllable::is_standard;
+use crate::Pinyin;
+use std::fmt::{self, Display, Formatter};
+
+/// Code points of the Han script, including CJK radicals, `〇` and the unified and
+/// compatibility ideographs of all planes.
+const HAN: [(u32, u32); 16] = [
+    (0x2E80, 0x2EF3),
+    (0x2F00, 0x2FD5),
+    (0x3005, 0x3005),
+    (0x3007, 0x3007),
+    (0x3021, 0x3029),
+    (0x3038, 0x303B),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xF900, 0xFAFF),
+    (0x16FE2, 0x16FE3),
+    (0x16FF0, 0x16FF1),
+    (0x20000, 0x2A6DF),
+    (0x2A700, 0x2EE5D),
+    (0x2F800, 0x2FA1F),
+    (0x30000, 0x3134F),
+    (0x31350, 0x323AF),
+];
+
+/// Whether `c` is a Han character.
+pub fn is_han(c: char) -> bool {
+    let c = c as u32;
+    HAN.iter().any(|&(start, end)| (start..=end).contains(&c))
+}
+
+/// Kind of a problem found by [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// The line fails to load, such as an invalid syllable or a duplicate entry.
+    Error(ParseErrorReason),
+    /// The code point is not a Han character.
+    NonHan,
+    /// Readings beyond [`MAX_READINGS`] are dropped when loaded.
+    DroppedReadings { kept: usize, dropped: usize },
+    /// The syllable is not in the syllable table of standard Mandarin, such as `fiào`.
+    NonstandardSyllable,
+}
+
+/// A problem of a line of pinyin data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column in chars of `text`.
+    pub column: usize,
+    /// The offending text.
+    pub text: String,
+    pub kind: LintKind,
+}
+
+impl Display for Lint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            LintKind::Error(reason) => {
+                let e = super::ParseError {
+                    line: self.line,
+                    column: self.column,
+                    text: self.text.clone(),
+                    reason: reason.clone(),
+                };
+                write!(f, "{}", e)
+            }
+            LintKind::NonHan => write!(
+                f,
+                "line {}, column {}: '{}' is not a Han character",
+                self.line, self.column, self.text
+            ),
+            LintKind::DroppedReadings { kept, dropped } => write!(
+                f,
+                "line {}, column {}: {} readings of '{}' are dropped, only {} are kept",
+                self.line, self.column, dropped, self.text, kept
+            ),
+            LintKind::NonstandardSyllable => write!(
+                f,
+                "line {}, column {}: '{}' is not a standard Mandarin syllable",
+                self.line, self.column, self.text
+            ),
+        }
+    }
+}
+
+/// Check data in the syntax of `pinyin.txt`, see [`DB::load`]. Besides errors of loading,
+/// report non-Han code points, nonstandard syllables and readings that would be dropped.
+/// Problems are in the order of lines.
+pub fn lint(s: &str) -> Vec<Lint> {
+    let (entries, errors) = parse_entries(s);
+    let lines: Vec<&str> = s.strip_prefix('\u{feff}').unwrap_or(s).lines().collect();
+    let mut lints: Vec<Lint> = errors
+        .into_iter()
+        .map(|e| Lint {
+            line: e.line,
+            column: e.column,
+            text: e.text,
+            kind: LintKind::Error(e.reason),
+        })
+        .collect();
+    for (no, (ch, readings)) in entries {
+        let text = format!("U+{:04X}", ch as u32);
+        if !is_han(ch) {
+            lints.push(Lint {
+                line: no,
+                column: 1,
+                text: text.clone(),
+                kind: LintKind::NonHan,
+            });
+        }
+        if let Ok(Some((_, syllables))) = key_value(lines[no - 1], no) {
+            for r in syllables.split(',').map(str::trim) {
+                if !is_standard(r) {
+                    lints.push(Lint {
+                        line: no,
+                        column: column(lines[no - 1], r),
+                        text: r.to_string(),
+                        kind: LintKind::NonstandardSyllable,
+                    });
+                }
+            }
+        }
+        if readings.len() > MAX_READINGS {
+            lints.push(Lint {
+                line: no,
+                column: 1,
+                text,
+                kind: LintKind::DroppedReadings {
+                    kept: MAX_READINGS,
+                    dropped: readings.len() - MAX_READINGS,
+                },
+            });
+        }
+    }
+    lints.sort_by_key(|l| (l.line, l.column));
+    lints
+}
+
+/// Difference of readings of a char between two databases.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Difference {
+    Added(char, Vec<Pinyin>),
+    Removed(char, Vec<Pinyin>),
+    Changed {
+        ch: char,
+        old: Vec<Pinyin>,
+        new: Vec<Pinyin>,
+    },
+}
+
+impl Difference {
+    pub fn char(&self) -> char {
+        match self {
+            Difference::Added(ch, _) | Difference::Removed(ch, _) => *ch,
+            Difference::Changed { ch, .. } => *ch,
+        }
+    }
+}
+
+fn join(readings: &[Pinyin]) -> String {
+    let readings: Vec<String> = readings.iter().map(Pinyin::to_string).collect();
+    readings.join(",")
+}
+
+impl Display for Difference {
+    /// One line in the style of unified diff, such as `+U+4E2D: zhōng  # 中`.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let line = |f: &mut Formatter<'_>, sign, ch: char, readings: &[Pinyin]| {
+            write!(
+                f,
+                "{}U+{:04X}: {}  # {}",
+                sign,
+                ch as u32,
+                join(readings),
+                ch
+            )
+        };
+        match self {
+            Difference::Added(ch, readings) => line(f, '+', *ch, readings),
+            Difference::Removed(ch, readings) => line(f, '-', *ch, readings),
+            Difference::Changed { ch, old, new } => {
+                line(f, '-', *ch, old)?;
+                writeln!(f)?;
+                line(f, '+', *ch, new)
+            }
+        }
+    }
+}
+
+/// Differences of char readings from `old` to `new`, in the order of code points.
+pub fn diff(old: &DB, new: &DB) -> Vec<Difference> {
+    let to_vec = |readings: &[u16]| Polyphone(readings).iter().collect::<Vec<_>>();
+    let mut result = vec![];
+    for (ch, readings) in old.entries() {
+        match new.readings(ch) {
+            [] => result.push(Difference::Removed(ch, to_vec(readings))),
+            r if r != readings => result.push(Difference::Changed {
+                ch,
+                old: to_vec(readings),
+                new: to_vec(r),
+            }),
+            _ => {}
+        }
+    }
+    for (ch, readings) in new.entries() {
+        if old.readings(ch).is_empty() {
+            result.push(Difference::Added(ch, to_vec(readings)));
+        }
+    }
+    result.sort_by_key(Difference::char);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case('中', true)]
+    #[case('〇', true)]
+    #[case('\u{20BBE}', true)]
+    #[case('\u{2F800}', true)]
+    #[case('a', false)]
+    #[case('。', false)]
+    #[case('\u{E815}', false)]
+    fn han(#[case] c: char, #[case] exp: bool) {
+        assert_eq!(is_han(c), exp);
+    }
+
+    #[test]
+    fn lint_data() {
+        let data = "U+4E2D: zhōng\nU+0041: a\nU+4E2D: zhòng\nU+548C: hé,xyz\nU+4E3A: wèi,wéi\n\
+                    U+8985: fiào\nU+4E00: yī,shiang,bü\n";
+        let lints: Vec<_> = lint(data).into_iter().map(|l| (l.line, l.kind)).collect();
+        let mut exp = vec![
+            (2, LintKind::NonHan),
+            (
+                3,
+                LintKind::Error(ParseErrorReason::DuplicateEntry { first_line: 1 }),
+            ),
+            (4, LintKind::Error(ParseErrorReason::UnknownSyllable)),
+        ];
+        if MAX_READINGS == 1 {
+            exp.push((
+                5,
+                LintKind::DroppedReadings {
+                    kept: 1,
+                    dropped: 1,
+                },
+            ));
+        }
+        exp.push((6, LintKind::NonstandardSyllable));
+        if MAX_READINGS == 1 {
+            exp.push((
+                7,
+                LintKind::DroppedReadings {
+                    kept: 1,
+                    dropped: 2,
+                },
+            ));
+        }
+        exp.push((7, LintKind::NonstandardSyllable));
+        exp.push((7, LintKind::NonstandardSyllable));
+        assert_eq!(lints, exp);
+        let lints = lint(data);
+        assert_eq!(
+            lints[0].to_string(),
+            "line 2, column 1: 'U+0041' is not a Han character"
+        );
+        let nonstandard: Vec<_> = lints
+            .iter()
+            .filter(|l| l.kind == LintKind::NonstandardSyllable)
+            .map(|l| l.to_string())
+            .collect();
+        assert_eq!(
+            nonstandard,
+            [
+                "line 6, column 9: 'fiào' is not a standard Mandarin syllable",
+                "line 7, column 12: 'shiang' is not a standard Mandarin syllable",
+                "line 7, column 19: 'bü' is not a standard Mandarin syllable",
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_data() {
+        let old = DB::load("U+4E2D: zhōng\nU+4E00: yī\nU+548C: hé\n").unwrap();
+        let new = DB::load("U+4E2D: zhòng\nU+548C: hé\nU+56FD: guó\n").unwrap();
+        let diffs: Vec<_> = diff(&old, &new).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diffs,
+            [
+                "-U+4E00: yī  # 一",
+                "-U+4E2D: zhōng  # 中\n+U+4E2D: zhòng  # 中",
+                "+U+56FD: guó  # 国",
+            ]
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+}
@@ -1,177 +1,323 @@
-use crate::db::{Polyphone, DB};
-use crate::pinyin::{py, FinalWithTones, Initials};
+use crate::db::DB;
+use crate::user_dict::UserDictEntry;
 use crate::Pinyin;
 use nom::{
-    branch::alt,
     bytes::complete::tag,
-    bytes::streaming::is_not,
-    character::complete::{char, hex_digit1, newline, space0},
-    combinator::{map_res, opt, value},
-    multi::{many0, separated_list1},
-    sequence::{pair, preceded, separated_pair, terminated, tuple},
-    IResult, InputTakeAtPosition,
+    character::complete::hex_digit1,
+    combinator::{all_consuming, map_opt},
+    sequence::preceded,
+    IResult,
 };
-use std::num::ParseIntError;
-use std::str::FromStr;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::hash::Hash;
 
-fn comment(i: &str) -> IResult<&str, Option<(char, Polyphone)>> {
-    value(None, tuple((space0, char('#'), opt(is_not("\n")), newline)))(i)
+/// Why a line of pinyin data failed to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorReason {
+    /// Line is not in the form of `U+XXXX: reading,reading` or `phrase: reading reading`,
+    /// as there is no `:`.
+    Syntax,
+    /// Nothing before the `:` of the line.
+    EmptyKey,
+    /// A Unihan line has less than 3 tab separated fields.
+    MissingField,
+    /// Not a `U+XXXX` code point of a valid char.
+    BadCodePoint,
+    UnknownSyllable,
+    /// The char or phrase is already defined at `first_line`.
+    DuplicateEntry {
+        first_line: usize,
+    },
+    /// Number of readings of a phrase differs from its number of chars.
+    ReadingCount {
+        chars: usize,
+        readings: usize,
+    },
 }
 
-fn code_point(i: &str) -> IResult<&str, char> {
+/// Error of a line of pinyin data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column in chars of `text`.
+    pub column: usize,
+    /// The offending text.
+    pub text: String,
+    pub reason: ParseErrorReason,
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.reason {
+            ParseErrorReason::Syntax => write!(f, "missing ':' in '{}'", self.text),
+            ParseErrorReason::EmptyKey => write!(f, "nothing before ':' in '{}'", self.text),
+            ParseErrorReason::MissingField => {
+                write!(f, "less than 3 tab separated fields in '{}'", self.text)
+            }
+            ParseErrorReason::BadCodePoint => write!(f, "bad code point '{}'", self.text),
+            ParseErrorReason::UnknownSyllable => write!(f, "unknown syllable '{}'", self.text),
+            ParseErrorReason::ReadingCount { chars, readings } => write!(
+                f,
+                "phrase '{}' has {} chars but {} readings",
+                self.text, chars, readings
+            ),
+            ParseErrorReason::DuplicateEntry { first_line } => {
+                write!(
+                    f,
+                    "duplicate entry '{}', first defined at line {}",
+                    self.text, first_line
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// All errors of pinyin data, there is at least one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseErrors(pub Vec<ParseError>);
+
+impl Display for ParseErrors {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (i, e) in self.0.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", e)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseErrors {}
+
+pub(super) fn code_point(i: &str) -> IResult<&str, char> {
     // parse a char in the form of U+XXXX
     preceded(
         tag("U+"),
-        map_res(hex_digit1, |s: &str| -> Result<char, ParseIntError> {
-            let code_point = u32::from_str_radix(s, 16)?;
-            Ok(char::from_u32(code_point).unwrap())
+        map_opt(hex_digit1, |s: &str| {
+            u32::from_str_radix(s, 16).ok().and_then(char::from_u32)
         }),
     )(i)
 }
 
-fn initials(i: &str) -> IResult<&str, Initials> {
-    alt((
-        value(Initials::B, char('b')),
-        value(Initials::CH, tag("ch")),
-        value(Initials::C, char('c')),
-        value(Initials::D, char('d')),
-        value(Initials::F, char('f')),
-        value(Initials::G, char('g')),
-        value(Initials::H, char('h')),
-        value(Initials::J, char('j')),
-        value(Initials::K, char('k')),
-        value(Initials::L, char('l')),
-        value(Initials::M, char('m')),
-        value(Initials::N, char('n')),
-        value(Initials::P, char('p')),
-        value(Initials::Q, char('q')),
-        value(Initials::R, char('r')),
-        value(Initials::SH, tag("sh")),
-        value(Initials::S, char('s')),
-        value(Initials::T, char('t')),
-        value(Initials::W, char('w')),
-        value(Initials::X, char('x')),
-        alt((
-            value(Initials::Y, char('y')),
-            value(Initials::ZH, tag("zh")),
-            value(Initials::Z, char('z')),
-        )),
-    ))(i)
+/// 1-based column in chars of `text`, a sub slice of `line`.
+pub(super) fn column(line: &str, text: &str) -> usize {
+    let start = text.as_ptr() as usize - line.as_ptr() as usize;
+    line[..start].chars().count() + 1
 }
 
-#[rustfmt::skip]
-fn is_pinyin_char(c: char) -> bool {
-    if c.is_ascii_alphabetic() {
-        true
-    } else {
-        matches!(c,
-            'ā' | 'á' | 'ǎ' | 'à' |
-            'ē' | 'é' | 'ě' | 'è' |
-            'ī' | 'í' | 'ǐ' | 'ì' |
-            'ō' | 'ó' | 'ǒ' | 'ò' |
-            'ū' | 'ú' | 'ǔ' | 'ù' |
-            'ǖ' | 'ǘ' | 'ǚ' | 'ǜ' |
-            'ü' |
-            'ń' | 'ň' | 'ǹ'
-        )
+/// Build the error of `text`, a sub slice of `line`.
+pub(super) fn line_error(
+    line: &str,
+    no: usize,
+    text: &str,
+    reason: ParseErrorReason,
+) -> ParseError {
+    ParseError {
+        line: no,
+        column: column(line, text),
+        text: text.to_string(),
+        reason,
     }
 }
 
-fn final_and_tones(i: &str) -> IResult<&str, FinalWithTones> {
-    let (i, s) =
-        i.split_at_position1_complete(|x| !is_pinyin_char(x), nom::error::ErrorKind::Alpha)?;
-    let r = FinalWithTones::from_str(s);
-    match r {
-        Ok(r) => Ok((i, r)),
-        Err(_) => Err(nom::Err::Error(nom::error::Error::new(
-            i,
-            nom::error::ErrorKind::Tag,
-        ))),
+/// Split a line to key and value by `: `, with comment and surrounding spaces removed.
+/// Return `None` for empty or comment line.
+pub(super) fn key_value(line: &str, no: usize) -> Result<Option<(&str, &str)>, Vec<ParseError>> {
+    let content = line.split('#').next().unwrap().trim();
+    if content.is_empty() {
+        return Ok(None);
     }
+    let reason = match content.split_once(':') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            return Ok(Some((key.trim(), value.trim())))
+        }
+        Some(_) => ParseErrorReason::EmptyKey,
+        None => ParseErrorReason::Syntax,
+    };
+    Err(vec![line_error(line, no, content, reason)])
 }
 
-fn pinyin(i: &str) -> IResult<&str, Pinyin> {
-    let (i, (initials, final_with_tones)) = pair(opt(initials), final_and_tones)(i)?;
-    Ok((
-        i,
-        py(
-            initials.unwrap_or(Initials::None),
-            final_with_tones.0,
-            final_with_tones.1,
-        ),
-    ))
+/// Parse readings, record errors of unknown syllables.
+pub(super) fn readings<'a>(
+    line: &str,
+    no: usize,
+    syllables: impl Iterator<Item = &'a str>,
+    errors: &mut Vec<ParseError>,
+) -> Vec<Pinyin> {
+    syllables
+        .filter_map(|r| match r.parse() {
+            Ok(p) => Some(p),
+            Err(_) => {
+                errors.push(line_error(line, no, r, ParseErrorReason::UnknownSyllable));
+                None
+            }
+        })
+        .collect()
 }
 
-fn empty_line(i: &str) -> IResult<&str, Option<(char, Polyphone)>> {
-    value(None, pair(space0, newline))(i)
+/// Parse a line of pinyin data such as `U+4E2D: zhōng,zhòng  # 中`, return `None` for empty
+/// or comment line. `no` is the 1-based line number.
+fn parse_line(line: &str, no: usize) -> Result<Option<(char, Vec<Pinyin>)>, Vec<ParseError>> {
+    let Some((code, syllables)) = key_value(line, no)? else {
+        return Ok(None);
+    };
+    let mut errors = vec![];
+    let ch = match all_consuming(code_point)(code) {
+        Ok((_, ch)) => Some(ch),
+        Err(_) => {
+            errors.push(line_error(line, no, code, ParseErrorReason::BadCodePoint));
+            None
+        }
+    };
+    let readings = readings(line, no, syllables.split(','), &mut errors);
+    match ch {
+        Some(ch) if errors.is_empty() => Ok(Some((ch, readings))),
+        _ => Err(errors),
+    }
 }
 
-fn parse_line(i: &str) -> IResult<&str, Option<(char, Polyphone)>> {
-    let pn_list = separated_list1(char(','), pinyin);
-    let char_and_pinyin = separated_pair(code_point, tag(": "), pn_list);
-    let mut line = terminated(char_and_pinyin, alt((comment, empty_line)));
-    let (remains, (ch, pinyin_list)) = line(i)?;
-    Ok((remains, Some((ch, pinyin_list.into()))))
+/// Parse a line of phrase data such as `中国: zhōng guó`, return `None` for empty or comment
+/// line. `no` is the 1-based line number.
+fn parse_phrase_line(
+    line: &str,
+    no: usize,
+) -> Result<Option<(String, Vec<Pinyin>)>, Vec<ParseError>> {
+    let Some((phrase, syllables)) = key_value(line, no)? else {
+        return Ok(None);
+    };
+    let mut errors = vec![];
+    let readings = readings(line, no, syllables.split_whitespace(), &mut errors);
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    let chars = phrase.chars().count();
+    if chars != readings.len() {
+        let reason = ParseErrorReason::ReadingCount {
+            chars,
+            readings: readings.len(),
+        };
+        return Err(vec![line_error(line, no, phrase, reason)]);
+    }
+    Ok(Some((phrase.to_string(), readings)))
 }
 
-fn parse_lines(
+/// Parse lines by `parse_line`, return entries with their line numbers, and errors of all
+/// bad lines.
+fn parse_lines<T>(
     i: &str,
-) -> Result<impl Iterator<Item = (char, Polyphone)>, nom::Err<nom::error::Error<&str>>> {
-    let (remains, lines) = many0(alt((empty_line, comment, parse_line)))(i)?;
-    if !remains.is_empty() {
-        panic!(
-            "remains: {}",
-            remains.chars().into_iter().take(10).collect::<String>()
-        );
+    parse_line: impl Fn(&str, usize) -> Result<Option<T>, Vec<ParseError>>,
+) -> (Vec<(usize, T)>, Vec<ParseError>) {
+    let mut entries = vec![];
+    let mut errors = vec![];
+    // upstream files may start with a byte order mark
+    let i = i.strip_prefix('\u{feff}').unwrap_or(i);
+    for (idx, line) in i.lines().enumerate() {
+        match parse_line(line, idx + 1) {
+            Ok(Some(entry)) => entries.push((idx + 1, entry)),
+            Ok(None) => {}
+            Err(e) => errors.extend(e),
+        }
     }
-    Ok(lines.into_iter().flatten())
+    (entries, errors)
 }
 
-pub fn parse_db(i: &str) -> Result<DB, nom::error::Error<&str>> {
-    match parse_lines(i) {
-        Ok(iter) => {
-            let mut db = DB::new();
-            for (ch, polyphone) in iter {
-                db.insert(ch, polyphone);
-            }
-            db.shrink_to_fit();
-            Ok(db)
+/// Report entries defined more than once, keep the first definition.
+fn dedup<K: Hash + Eq + Clone, V>(
+    entries: Vec<(usize, (K, V))>,
+    errors: &mut Vec<ParseError>,
+    display: impl Fn(&K) -> String,
+) -> Vec<(usize, (K, V))> {
+    let mut first_lines: HashMap<K, usize> = HashMap::with_capacity(entries.len());
+    let mut result = Vec::with_capacity(entries.len());
+    for (no, (key, value)) in entries {
+        if let Some(&first_line) = first_lines.get(&key) {
+            errors.push(ParseError {
+                line: no,
+                column: 1,
+                text: display(&key),
+                reason: ParseErrorReason::DuplicateEntry { first_line },
+            });
+            continue;
         }
-        Err(nom::Err::Incomplete(_)) => unreachable!(),
-        Err(nom::Err::Error(e)) => Err(e),
-        Err(nom::Err::Failure(e)) => Err(e),
+        first_lines.insert(key.clone(), no);
+        result.push((no, (key, value)));
+    }
+    result
+}
+
+pub(super) fn into_result<T>(value: T, mut errors: Vec<ParseError>) -> Result<T, ParseErrors> {
+    if errors.is_empty() {
+        Ok(value)
+    } else {
+        errors.sort_by_key(|e| (e.line, e.column));
+        Err(ParseErrors(errors))
+    }
+}
+
+/// Parse a line of a user dictionary, either a char such as `U+4E2D: zhōng,zhòng` or a
+/// phrase such as `中国: zhōng guó`.
+fn parse_user_dict_line(line: &str, no: usize) -> Result<Option<UserDictEntry>, Vec<ParseError>> {
+    if line.trim_start().starts_with("U+") {
+        Ok(parse_line(line, no)?.map(|(ch, readings)| UserDictEntry::Char(ch, readings)))
+    } else {
+        let entry = parse_phrase_line(line, no)?;
+        Ok(entry.map(|(phrase, readings)| UserDictEntry::Phrase(phrase, readings)))
+    }
+}
+
+/// Parse a user dictionary, later entries override earlier ones.
+pub(crate) fn parse_user_dict(i: &str) -> Result<Vec<UserDictEntry>, ParseErrors> {
+    let (entries, errors) = parse_lines(i, parse_user_dict_line);
+    into_result(entries.into_iter().map(|(_, e)| e).collect(), errors)
+}
+
+/// Chars and their readings with line numbers.
+pub(super) type Entries = Vec<(usize, (char, Vec<Pinyin>))>;
+
+/// Parse chars and their readings, and errors of all bad lines and duplicate entries.
+pub(super) fn parse_entries(i: &str) -> (Entries, Vec<ParseError>) {
+    let (entries, mut errors) = parse_lines(i, parse_line);
+    let entries = dedup(entries, &mut errors, |ch| format!("U+{:04X}", *ch as u32));
+    (entries, errors)
+}
+
+pub fn parse_db(i: &str) -> Result<DB, ParseErrors> {
+    let (entries, errors) = parse_entries(i);
+    let mut db = DB::new();
+    for (_, (ch, readings)) in entries {
+        db.insert(ch, &readings);
     }
+    db.shrink_to_fit();
+    into_result(db, errors)
+}
+
+/// Parse phrase data in the syntax of mozillazg/phrase-pinyin-data, such as
+/// `中国: zhōng guó`.
+pub(crate) fn parse_phrases(i: &str) -> Result<Vec<(String, Vec<Pinyin>)>, ParseErrors> {
+    let (entries, mut errors) = parse_lines(i, parse_phrase_line);
+    let entries = dedup(entries, &mut errors, String::clone);
+    into_result(entries.into_iter().map(|(_, e)| e).collect(), errors)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::pinyin::{Finals, Tones};
+    use crate::pinyin::{py, Finals, Initials, Tones};
+    use crate::syllable::is_pinyin_char;
     use rstest::rstest;
 
-    #[test]
-    fn parse_comment() {
-        assert_eq!(comment("#\n"), Ok(("", None)));
-        assert_eq!(comment("# Hello world\n"), Ok(("", None)));
-        assert_eq!(comment("  # Hello world\n"), Ok(("", None)));
-        assert_eq!(comment("# Hello world\r\n"), Ok(("", None)));
-    }
-
     #[test]
     fn parse_code_point() {
         assert_eq!(code_point("U+4E2D"), Ok(("", '中')));
         assert_eq!(code_point("U+2104C"), Ok(("", '𡁌')));
     }
 
-    #[test]
-    fn parse_initials() {
-        assert_eq!(initials("b"), Ok(("", Initials::B)));
-        assert_eq!(initials("ch"), Ok(("", Initials::CH)));
-        assert_eq!(initials("c"), Ok(("", Initials::C)));
-        assert_eq!(initials("y"), Ok(("", Initials::Y)));
-        assert_eq!(initials("zh"), Ok(("", Initials::ZH)));
-    }
-
     #[test]
     fn test_is_py_chr() {
         assert!(is_pinyin_char('a'));
@@ -180,81 +326,104 @@ mod tests {
         assert!(is_pinyin_char('ǔ'));
         assert!(is_pinyin_char('ǜ'));
         assert!(is_pinyin_char('ú'));
-    }
-
-    #[rstest]
-    #[case("a", Finals::A, Tones::None)]
-    #[case("á", Finals::A, Tones::Two)]
-    #[case("ang", Finals::Ang, Tones::None)]
-    #[case("ǎn", Finals::AN, Tones::Three)]
-    #[case("ún", Finals::UN, Tones::Two)]
-    fn parse_final_and_tones(#[case] s: &str, #[case] finals: Finals, #[case] tones: Tones) {
-        assert_eq!(final_and_tones(s), Ok(("", FinalWithTones(finals, tones))));
+        assert!(is_pinyin_char('ê'));
+        assert!(is_pinyin_char('\u{304}'));
+        assert!(!is_pinyin_char(','));
     }
 
     #[rstest]
     #[case("a", Initials::None, Finals::A, Tones::None)]
     #[case("bá", Initials::B, Finals::A, Tones::Two)]
     #[case("hún", Initials::H, Finals::UN, Tones::Two)]
+    #[case("ba", Initials::B, Finals::A, Tones::None)]
+    #[case("chá", Initials::CH, Finals::A, Tones::Two)]
+    #[case("cā", Initials::C, Finals::A, Tones::One)]
+    #[case("yǎ", Initials::Y, Finals::A, Tones::Three)]
+    #[case("zhà", Initials::ZH, Finals::A, Tones::Four)]
+    #[case("ang", Initials::None, Finals::Ang, Tones::None)]
+    #[case("ǎn", Initials::None, Finals::AN, Tones::Three)]
+    #[case("ń", Initials::None, Finals::N, Tones::Two)]
+    #[case("m\u{304}", Initials::None, Finals::M, Tones::One)]
     fn parse_pinyin(
         #[case] s: &str,
         #[case] initials: Initials,
         #[case] finals: Finals,
         #[case] tones: Tones,
     ) {
-        assert_eq!(pinyin(s), Ok(("", py(initials, finals, tones))));
+        assert_eq!(s.parse::<Pinyin>(), Ok(py(initials, finals, tones)));
+    }
+
+    #[test]
+    fn pinyin_from_str() {
+        assert_eq!(
+            "zhōng".parse::<Pinyin>(),
+            Ok(py(Initials::ZH, Finals::Ong, Tones::One))
+        );
+        assert!("zhōng ".parse::<Pinyin>().is_err());
+        assert!("xyz".parse::<Pinyin>().is_err());
     }
 
     #[test]
     fn test_parse_line() {
         assert_eq!(
-            parse_line("U+4E2D: zhōng\n"),
-            Ok((
-                "",
-                Some((
-                    '中',
-                    Polyphone::from(vec![py(Initials::ZH, Finals::Ong, Tones::One)])
-                ))
-            ))
+            parse_line("U+4E2D: zhōng", 1),
+            Ok(Some((
+                '中',
+                vec![py(Initials::ZH, Finals::Ong, Tones::One)]
+            )))
         );
         assert_eq!(
-            parse_line("U+4E2D: zhōng,zhòng\n"),
-            Ok((
-                "",
-                Some((
-                    '中',
-                    Polyphone::from(vec![
-                        py(Initials::ZH, Finals::Ong, Tones::One),
-                        py(Initials::ZH, Finals::Ong, Tones::Four)
-                    ])
-                ))
-            ))
+            parse_line("U+4E2D: zhōng,zhòng", 1),
+            Ok(Some((
+                '中',
+                vec![
+                    py(Initials::ZH, Finals::Ong, Tones::One),
+                    py(Initials::ZH, Finals::Ong, Tones::Four)
+                ]
+            )))
         );
         assert_eq!(
-            parse_line("U+4E2D: zhōng,zhòng # comment\n"),
-            Ok((
-                "",
-                Some((
-                    '中',
-                    Polyphone::from(vec![
-                        py(Initials::ZH, Finals::Ong, Tones::One),
-                        py(Initials::ZH, Finals::Ong, Tones::Four),
-                    ])
-                ))
-            ))
+            parse_line("U+4E2D: zhōng,zhòng # comment", 1),
+            Ok(Some((
+                '中',
+                vec![
+                    py(Initials::ZH, Finals::Ong, Tones::One),
+                    py(Initials::ZH, Finals::Ong, Tones::Four),
+                ]
+            )))
         );
     }
 
-    #[test]
-    fn parse_empty_line() {
-        assert_eq!(empty_line("\n"), Ok(("", None)));
-        assert_eq!(empty_line(" \t\n"), Ok(("", None)));
-        assert!(matches!(empty_line("foo"), Err(_)));
+    #[rstest]
+    #[case("", None)]
+    #[case("  # comment", None)]
+    #[case(
+        "U+4E2D zhōng",
+        Some((1, "U+4E2D zhōng".to_string(), ParseErrorReason::Syntax))
+    )]
+    #[case(": zhōng", Some((1, ": zhōng".to_string(), ParseErrorReason::EmptyKey)))]
+    #[case("U+D800: zhōng", Some((1, "U+D800".to_string(), ParseErrorReason::BadCodePoint)))]
+    #[case("4E2D: zhōng", Some((1, "4E2D".to_string(), ParseErrorReason::BadCodePoint)))]
+    #[case("U+4E2D: zhōng,xyz", Some((15, "xyz".to_string(), ParseErrorReason::UnknownSyllable)))]
+    fn parse_bad_line(#[case] line: &str, #[case] exp: Option<(usize, String, ParseErrorReason)>) {
+        let r = parse_line(line, 3).map_err(|mut e| {
+            let e = e.remove(0);
+            assert_eq!(e.line, 3);
+            (e.column, e.text, e.reason)
+        });
+        match exp {
+            None => assert_eq!(r, Ok(None)),
+            Some(exp) => assert_eq!(r, Err(exp)),
+        }
     }
 
     #[test]
     fn test_parse_lines() {
-        let parse = |s: &str| parse_lines(s).unwrap().collect::<Vec<_>>();
+        let parse = |s: &str| {
+            let (entries, errors) = parse_lines(s, parse_line);
+            assert_eq!(errors, vec![]);
+            entries.into_iter().map(|(_, e)| e).collect::<Vec<_>>()
+        };
 
         // parse empty
         assert_eq!(parse(""), vec![]);
@@ -269,17 +438,66 @@ U+3007: líng,yuán,xīng  # 〇
 "#
             ),
             vec![
+                ('中', vec![py(Initials::ZH, Finals::Ong, Tones::One)]),
                 (
-                    '中',
-                    Polyphone(py(Initials::ZH, Finals::Ong, Tones::One).into(), 0, 0)
+                    '〇',
+                    vec![
+                        py(Initials::L, Finals::Ing, Tones::Two),
+                        py(Initials::Y, Finals::Uan, Tones::Two),
+                        py(Initials::X, Finals::Ing, Tones::One),
+                    ]
                 ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_user_dict() {
+        let zhong = py(Initials::ZH, Finals::Ong, Tones::One);
+        let guo = py(Initials::G, Finals::UO, Tones::Two);
+        assert_eq!(
+            parse_user_dict(
+                r#"# comment
+U+4E2D: zhōng,guó  # 中
+
+中国: zhōng guó
+"#
+            ),
+            Ok(vec![
+                UserDictEntry::Char('中', vec![zhong, guo]),
+                UserDictEntry::Phrase("中国".to_string(), vec![zhong, guo]),
+            ])
+        );
+        assert_eq!(
+            parse_user_dict("U+4E2D: zhōng\r\n中国: zhōng guó\r\n"),
+            Ok(vec![
+                UserDictEntry::Char('中', vec![zhong]),
+                UserDictEntry::Phrase("中国".to_string(), vec![zhong, guo]),
+            ])
+        );
+        assert_eq!(
+            parse_user_dict("中国: zhōng guó"),
+            Ok(vec![UserDictEntry::Phrase(
+                "中国".to_string(),
+                vec![zhong, guo]
+            )])
+        );
+
+        let errors = parse_user_dict("U+4E2D: zhōng\n中国 zhōng guó\n中国: zhōng\n")
+            .err()
+            .unwrap()
+            .0;
+        let errors: Vec<_> = errors.iter().map(|e| (e.line, e.reason.clone())).collect();
+        assert_eq!(
+            errors,
+            vec![
+                (2, ParseErrorReason::Syntax),
                 (
-                    '〇',
-                    Polyphone(
-                        py(Initials::L, Finals::Ing, Tones::Two).into(),
-                        py(Initials::Y, Finals::Uan, Tones::Two).into(),
-                        py(Initials::X, Finals::Ing, Tones::One).into()
-                    )
+                    3,
+                    ParseErrorReason::ReadingCount {
+                        chars: 2,
+                        readings: 1
+                    }
                 ),
             ]
         );
@@ -287,10 +505,49 @@ U+3007: líng,yuán,xīng  # 〇
 
     #[test]
     fn test_parse_db() {
-        let count = parse_lines(include_str!("../pinyin.txt")).unwrap().count();
+        let count = parse_lines(include_str!("../pinyin.txt"), parse_line)
+            .0
+            .len();
         assert!(count > 20902, "count = {}", count);
         let db = parse_db(include_str!("../pinyin.txt")).unwrap();
         let po: Pinyin = db.get('𰻞').unwrap().into();
         assert_eq!(po, py(Initials::B, Finals::Iang, Tones::Two));
     }
+
+    #[test]
+    fn collect_all_errors() {
+        let data = "U+4E2D: zhōng\r\nU+4E2D: zhòng\nbad line\nU+4E2E: x,zh\n : hé\n";
+        let errors = parse_db(data).err().unwrap().0;
+        let errors: Vec<_> = errors
+            .iter()
+            .map(|e| (e.line, e.column, e.to_string()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (
+                    2,
+                    1,
+                    "line 2, column 1: duplicate entry 'U+4E2D', first defined at line 1"
+                        .to_string()
+                ),
+                (
+                    3,
+                    1,
+                    "line 3, column 1: missing ':' in 'bad line'".to_string()
+                ),
+                (4, 9, "line 4, column 9: unknown syllable 'x'".to_string()),
+                (
+                    4,
+                    11,
+                    "line 4, column 11: unknown syllable 'zh'".to_string()
+                ),
+                (
+                    5,
+                    2,
+                    "line 5, column 2: nothing before ':' in ': hé'".to_string()
+                ),
+            ]
+        );
+    }
 }
@@ -0,0 +1,160 @@
+//! Chars of a reading, the reverse of the database that is keyed by code point.
+use super::DB;
+use crate::pinyin::PinyinDisplay;
+use crate::Pinyin;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+
+/// Index from readings to chars, such as `zhōng` to `中`, `钟` and `忠`. All readings of a
+/// char are indexed, chars of a query are in the order of code points.
+#[derive(Debug, Clone, Default)]
+pub struct ReverseIndex {
+    exact: BTreeMap<u16, Vec<char>>,
+    /// Keyed by readings without tone.
+    toneless: BTreeMap<u16, Vec<char>>,
+    /// Indexed by the first letter of readings, `a` to `z`.
+    letters: [Vec<char>; 26],
+}
+
+/// Push `c` unless it is the last one, chars come in the order of code points so each char
+/// is kept once.
+fn push(chars: &mut Vec<char>, c: char) {
+    if chars.last() != Some(&c) {
+        chars.push(c);
+    }
+}
+
+impl ReverseIndex {
+    pub fn new(db: &DB) -> Self {
+        let mut r = Self::default();
+        for (c, readings) in db.iter() {
+            for p in readings.iter() {
+                push(r.exact.entry(p.into()).or_default(), c);
+                push(r.toneless.entry(p.toneless().into()).or_default(), c);
+                let letter = PinyinDisplay::FirstLetter(p).pieces().next();
+                if let Some(i) = letter.and_then(|l| letter_index(l.as_bytes()[0] as char)) {
+                    push(&mut r.letters[i], c);
+                }
+            }
+        }
+        r
+    }
+
+    /// Chars that read `reading` with the same tone, a reading without tone matches chars of
+    /// the neutral tone, such as `ma` for `吗`.
+    pub fn chars(&self, reading: Pinyin) -> &[char] {
+        self.exact.get(&reading.into()).map_or(&[], Vec::as_slice)
+    }
+
+    /// Chars that read `reading` in any tone, such as `zhong` for `中`, `肿` and `重`.
+    pub fn chars_toneless(&self, reading: Pinyin) -> &[char] {
+        self.toneless
+            .get(&reading.toneless().into())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Readings without tone that have chars, in the order of their `u16` representation.
+    pub fn syllables(&self) -> impl Iterator<Item = Pinyin> + '_ {
+        self.toneless.keys().map(|&p| Pinyin::from(p))
+    }
+
+    /// Chars that have a reading starts with `letter`, such as `z` for readings of both `z`
+    /// and `zh` initials. Letters are case insensitive.
+    pub fn chars_by_initial(&self, letter: char) -> &[char] {
+        letter_index(letter).map_or(&[], |i| self.letters[i].as_slice())
+    }
+}
+
+fn letter_index(letter: char) -> Option<usize> {
+    let letter = letter.to_ascii_lowercase();
+    letter
+        .is_ascii_lowercase()
+        .then_some(letter as usize - 'a' as usize)
+}
+
+impl From<&DB> for ReverseIndex {
+    fn from(db: &DB) -> Self {
+        Self::new(db)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn index() -> ReverseIndex {
+        let mut db = DB::new();
+        let mut insert = |c, readings: &[&str]| {
+            let readings: Vec<Pinyin> = readings.iter().map(|s| s.parse().unwrap()).collect();
+            db.insert(c, &readings);
+        };
+        insert('中', &["zhōng", "zhòng"]);
+        insert('重', &["zhòng", "chóng"]);
+        insert('钟', &["zhōng"]);
+        insert('肿', &["zhǒng"]);
+        insert('子', &["zǐ", "zi"]);
+        insert('吗', &["ma", "mǎ"]);
+        insert('安', &["ān"]);
+        ReverseIndex::new(&db)
+    }
+
+    #[rstest]
+    #[case("zhōng", "中钟")]
+    #[case("zǐ", "子")]
+    #[case("ma", "吗")]
+    #[case("guó", "")]
+    fn exact(#[case] reading: &str, #[case] exp: &str) {
+        let chars: String = index().chars(reading.parse().unwrap()).iter().collect();
+        assert_eq!(chars, exp);
+    }
+
+    #[rstest]
+    #[case("zhong", "中肿重钟")]
+    #[case("zhǒng", "中肿重钟")]
+    #[case("ma", "吗")]
+    fn toneless(#[case] reading: &str, #[case] exp: &str) {
+        let chars: String = index()
+            .chars_toneless(reading.parse().unwrap())
+            .iter()
+            .collect();
+        assert_eq!(chars, exp);
+    }
+
+    #[rstest]
+    #[case('z', "中子肿重钟")]
+    #[case('Z', "中子肿重钟")]
+    #[case('a', "安")]
+    #[case('x', "")]
+    #[case('中', "")]
+    fn by_initial(#[case] letter: char, #[case] exp: &str) {
+        let chars: String = index().chars_by_initial(letter).iter().collect();
+        assert_eq!(chars, exp);
+    }
+
+    #[test]
+    fn syllables() {
+        let syllables: Vec<String> = index().syllables().map(|p| p.to_string()).collect();
+        for s in ["an", "ma", "zhong", "zi"] {
+            assert_eq!(syllables.iter().filter(|p| *p == s).count(), 1);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "polyphone")]
+    fn all_readings() {
+        let index = index();
+        assert_eq!(index.chars("zhòng".parse().unwrap()), ['中', '重']);
+        assert_eq!(index.chars_toneless("chong".parse().unwrap()), ['重']);
+        assert_eq!(index.chars_by_initial('c'), ['重']);
+        assert_eq!(index.chars("zi".parse().unwrap()), ['子']);
+    }
+
+    #[test]
+    #[cfg(all(feature = "std", feature = "embedded-data"))]
+    fn embedded() {
+        let index = crate::db::embedded_reverse_index();
+        assert!(index.chars("zhōng".parse().unwrap()).contains(&'中'));
+        assert!(index.chars_toneless("zhong".parse().unwrap()).len() > 10);
+    }
+}
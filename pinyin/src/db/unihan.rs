@@ -0,0 +1,217 @@
+//! Import Mandarin readings from `Unihan_Readings.txt` of the Unicode Unihan database.
+use super::parser::{code_point, into_result, line_error, readings, ParseErrorReason};
+use super::{ParseErrors, DB};
+use crate::Pinyin;
+use nom::combinator::all_consuming;
+use std::collections::BTreeMap;
+
+/// Unihan fields of Mandarin readings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnihanField {
+    /// `kMandarin`, the most customary readings, such as `zhōng zhòng`.
+    Mandarin,
+    /// `kHanyuPinyin`, readings in 汉语大字典, such as `10019.010:zhōng,zhòng`.
+    HanyuPinyin,
+    /// `kXHC1983`, readings in 现代汉语词典, such as `1508.030:zhōng 1512.060:zhòng`.
+    XHC1983,
+    /// `kTGHZ2013`, readings in 通用规范汉字字典, such as `482.140:zhōng 485.060:zhòng`.
+    TGHZ2013,
+}
+
+impl UnihanField {
+    const ALL: [UnihanField; 4] = [
+        UnihanField::Mandarin,
+        UnihanField::HanyuPinyin,
+        UnihanField::XHC1983,
+        UnihanField::TGHZ2013,
+    ];
+
+    fn tag(self) -> &'static str {
+        match self {
+            UnihanField::Mandarin => "kMandarin",
+            UnihanField::HanyuPinyin => "kHanyuPinyin",
+            UnihanField::XHC1983 => "kXHC1983",
+            UnihanField::TGHZ2013 => "kTGHZ2013",
+        }
+    }
+}
+
+/// Which Unihan fields are imported and how their readings are combined.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnihanPolicy {
+    /// Fields to import, in the order of precedence.
+    pub fields: Vec<UnihanField>,
+    /// If true, readings of all fields are merged in the order of `fields`, otherwise only
+    /// readings of the first field that has readings of a char are used.
+    pub merge_fields: bool,
+}
+
+impl Default for UnihanPolicy {
+    /// Prefer the customary readings of `kMandarin`, then the modern dictionaries, then
+    /// 汉语大字典 that covers the most chars. Fields are not merged.
+    fn default() -> Self {
+        Self {
+            fields: vec![
+                UnihanField::Mandarin,
+                UnihanField::TGHZ2013,
+                UnihanField::XHC1983,
+                UnihanField::HanyuPinyin,
+            ],
+            merge_fields: false,
+        }
+    }
+}
+
+/// Syllables of a field value. Except `kMandarin`, each reading is prefixed with its
+/// locations in the dictionary, such as `10019.010:zhōng,zhòng`.
+fn syllables(field: UnihanField, value: &str) -> Vec<&str> {
+    let entries = value.split_whitespace();
+    match field {
+        UnihanField::Mandarin => entries.collect(),
+        _ => entries
+            .flat_map(|e| e.split_once(':').map_or(e, |(_, r)| r).split(','))
+            .collect(),
+    }
+}
+
+pub(crate) fn parse_unihan(i: &str, policy: &UnihanPolicy) -> Result<DB, ParseErrors> {
+    let mut fields: BTreeMap<char, [Vec<Pinyin>; 4]> = BTreeMap::new();
+    let mut errors = vec![];
+    let i = i.strip_prefix('\u{feff}').unwrap_or(i);
+    for (idx, line) in i.lines().enumerate() {
+        let no = idx + 1;
+        if line.starts_with('#') || line.trim().is_empty() {
+            continue;
+        }
+        let mut parts = line.trim_end().splitn(3, '\t');
+        let (Some(code), Some(tag), Some(value)) = (parts.next(), parts.next(), parts.next())
+        else {
+            errors.push(line_error(line, no, line, ParseErrorReason::MissingField));
+            continue;
+        };
+        // other fields in the file, such as `kCantonese`, are skipped
+        let Some(field) = UnihanField::ALL.into_iter().find(|f| f.tag() == tag) else {
+            continue;
+        };
+        if !policy.fields.contains(&field) {
+            continue;
+        }
+
+        let Ok((_, ch)) = all_consuming(code_point)(code) else {
+            errors.push(line_error(line, no, code, ParseErrorReason::BadCodePoint));
+            continue;
+        };
+        let syllables = syllables(field, value);
+        let field_readings = &mut fields.entry(ch).or_default()[field as usize];
+        for p in readings(line, no, syllables.into_iter(), &mut errors) {
+            if !field_readings.contains(&p) {
+                field_readings.push(p);
+            }
+        }
+    }
+
+    let mut db = DB::new();
+    for (ch, field_readings) in fields {
+        let mut merged: Vec<Pinyin> = vec![];
+        for field in &policy.fields {
+            for p in &field_readings[*field as usize] {
+                if !merged.contains(p) {
+                    merged.push(*p);
+                }
+            }
+            if !merged.is_empty() && !policy.merge_fields {
+                break;
+            }
+        }
+        if !merged.is_empty() {
+            db.insert(ch, &merged);
+        }
+    }
+    db.shrink_to_fit();
+    into_result(db, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MergeMode;
+
+    const DATA: &str = "# Unihan_Readings.txt\n\
+        U+4E2D\tkCantonese\tzung1 zung3\n\
+        U+4E2D\tkHanyuPinyin\t10019.010:zhōng,zhòng\n\
+        U+4E2D\tkMandarin\tzhōng\n\
+        U+4E2D\tkTGHZ2013\t482.140:zhōng 485.060:zhòng\n\
+        U+4E2D\tkXHC1983\t1508.030:zhōng 1512.060:zhòng\n\
+        U+3400\tkHanyuPinyin\t10001.010,74801.080:qiū\n\
+        U+2B820\tkTGHZ2013\t025.010:fú\n";
+
+    fn readings(db: &DB, c: char) -> Vec<String> {
+        db.readings(c)
+            .iter()
+            .map(|r| Pinyin::from(*r).to_string())
+            .collect()
+    }
+
+    #[test]
+    fn default_policy() {
+        let db = DB::from_unihan(DATA, &UnihanPolicy::default()).unwrap();
+        assert_eq!(readings(&db, '中'), ["zhōng"]);
+        assert_eq!(readings(&db, '㐀'), ["qiū"]);
+        assert_eq!(readings(&db, '\u{2B820}'), ["fú"]);
+    }
+
+    #[test]
+    #[cfg(feature = "polyphone")]
+    fn merge_fields() {
+        let policy = UnihanPolicy {
+            fields: vec![UnihanField::TGHZ2013, UnihanField::Mandarin],
+            merge_fields: true,
+        };
+        let db = DB::from_unihan(DATA, &policy).unwrap();
+        assert_eq!(readings(&db, '中'), ["zhōng", "zhòng"]);
+        // chars only in other fields are not imported
+        assert_eq!(readings(&db, '㐀'), Vec::<String>::new());
+    }
+
+    #[test]
+    #[cfg(feature = "polyphone")]
+    fn merge_with_pinyin_txt() {
+        let unihan = DB::from_unihan(DATA, &UnihanPolicy::default()).unwrap();
+        let base = DB::load("U+4E2D: zhòng\nU+4E00: yī\n").unwrap();
+
+        let mut db = base.clone();
+        db.merge(&unihan, MergeMode::FillMissing);
+        assert_eq!(readings(&db, '中'), ["zhòng"]);
+        assert_eq!(readings(&db, '㐀'), ["qiū"]);
+        assert_eq!(readings(&db, '一'), ["yī"]);
+
+        let mut db = base.clone();
+        db.merge(&unihan, MergeMode::Override);
+        assert_eq!(readings(&db, '中'), ["zhōng"]);
+
+        let mut db = base;
+        db.merge(&unihan, MergeMode::Union);
+        assert_eq!(readings(&db, '中'), ["zhòng", "zhōng"]);
+    }
+
+    #[test]
+    fn errors() {
+        let data = "U+4E2D\tkMandarin\txyz\nU+ZZ\tkMandarin\tzhōng\nU+4E2D kMandarin zhōng\n";
+        let errors = DB::from_unihan(data, &UnihanPolicy::default())
+            .err()
+            .unwrap();
+        let reasons: Vec<_> = errors
+            .0
+            .iter()
+            .map(|e| (e.line, e.reason.clone()))
+            .collect();
+        assert_eq!(
+            reasons,
+            vec![
+                (1, ParseErrorReason::UnknownSyllable),
+                (2, ParseErrorReason::BadCodePoint),
+                (3, ParseErrorReason::MissingField),
+            ]
+        );
+    }
+}
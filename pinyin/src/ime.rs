@@ -0,0 +1,537 @@
+//! Input method engine: convert pinyin typed without tones, such as `zhongguoren` or `zgr`,
+//! to Chinese text ranked by a language model.
+//!
+//! The input is split to syllables in all possible ways, a syllable may be partial such as
+//! `zh` or `g`, apostrophe separates syllables explicitly. Chars of each syllable and words
+//! that span syllables form a lattice, paths of the lattice are scored by the model and the
+//! best paths are kept by beam search.
+use crate::db::{ReverseIndex, DB};
+use crate::Pinyin;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt::{self, Display, Formatter};
+
+/// Longest syllable as typed, `zhuang`.
+const MAX_SYLLABLE_LEN: usize = 6;
+/// Chars of a syllable considered at a position, the most likely first.
+const CHARS_PER_SYLLABLE: usize = 16;
+/// Paths kept at each position of the input.
+const BEAM: usize = 32;
+/// Log probability added for each partial syllable, so complete syllables are preferred.
+const PARTIAL_PENALTY: f64 = -2.3;
+/// Log of the weight of a word when the bigram is unknown, by "stupid backoff".
+const BACKOFF: f64 = -0.9;
+
+/// Scores words by log probabilities, higher is more likely.
+pub trait LanguageModel {
+    /// Log probability of `word`, a char or a word of multiple chars.
+    fn word(&self, word: &str) -> f64;
+
+    /// Log probability of `word` following `prev`, the default ignores `prev`.
+    fn next(&self, prev: &str, word: &str) -> f64 {
+        let _ = prev;
+        self.word(word)
+    }
+
+    /// Words of multiple chars known by the model, they are candidates besides phrases of
+    /// the database.
+    fn words(&self) -> Vec<&str> {
+        vec![]
+    }
+}
+
+/// Error of a line of [`NgramModel::load`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelError {
+    /// 1-based line number.
+    pub line: usize,
+    pub text: String,
+}
+
+impl Display for ModelError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: expected `word count` or `word word count` in '{}'",
+            self.line, self.text
+        )
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+/// Model of word and word pair counts, such as counted from a local corpus.
+#[derive(Debug, Clone, Default)]
+pub struct NgramModel {
+    unigrams: HashMap<String, u64>,
+    bigrams: HashMap<String, HashMap<String, u64>>,
+    total: u64,
+}
+
+impl NgramModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `count` occurrences of `word`.
+    pub fn add(&mut self, word: &str, count: u64) {
+        *self.unigrams.entry(word.to_string()).or_default() += count;
+        self.total += count;
+    }
+
+    /// Add `count` occurrences of `word` following `prev`.
+    pub fn add_bigram(&mut self, prev: &str, word: &str, count: u64) {
+        *self
+            .bigrams
+            .entry(prev.to_string())
+            .or_default()
+            .entry(word.to_string())
+            .or_default() += count;
+    }
+
+    /// Load counts in lines of `word count` and `word word count` for word pairs, fields are
+    /// separated by whitespace, empty lines and lines start with `#` are ignored.
+    pub fn load(s: &str) -> Result<Self, ModelError> {
+        let mut model = Self::new();
+        for (idx, line) in s.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let count = fields.last().and_then(|c| c.parse().ok());
+            match (fields.as_slice(), count) {
+                ([word, _], Some(count)) => model.add(word, count),
+                ([prev, word, _], Some(count)) => model.add_bigram(prev, word, count),
+                _ => {
+                    return Err(ModelError {
+                        line: idx + 1,
+                        text: line.to_string(),
+                    })
+                }
+            }
+        }
+        Ok(model)
+    }
+}
+
+impl LanguageModel for NgramModel {
+    /// Relative frequency of `word`, an unknown word counts half.
+    fn word(&self, word: &str) -> f64 {
+        let count = self.unigrams.get(word).map_or(0.5, |&c| c as f64);
+        (count / (self.total + 1) as f64).ln()
+    }
+
+    fn next(&self, prev: &str, word: &str) -> f64 {
+        let count = self.bigrams.get(prev).and_then(|m| m.get(word));
+        match (count, self.unigrams.get(prev)) {
+            (Some(&c), Some(&prev_count)) if prev_count >= c => (c as f64 / prev_count as f64).ln(),
+            _ => BACKOFF + self.word(word),
+        }
+    }
+
+    fn words(&self) -> Vec<&str> {
+        self.unigrams
+            .keys()
+            .filter(|w| w.chars().nth(1).is_some())
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+/// A conversion of the whole input.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub text: String,
+    /// Log probability by the model, higher is better.
+    pub score: f64,
+}
+
+/// A word that spans multiple syllables.
+struct Word {
+    text: String,
+    /// Ids of possible syllables of each char.
+    syllables: Vec<Vec<usize>>,
+}
+
+/// Part of input that reads one or more syllables.
+struct Segment {
+    end: usize,
+    /// Ids of syllables it matches.
+    syllables: Vec<usize>,
+    partial: bool,
+}
+
+/// A word starts at a position of the input.
+struct Edge<'a> {
+    end: usize,
+    text: &'a str,
+    partials: usize,
+}
+
+struct Path {
+    score: f64,
+    text: String,
+    /// Start of the last word in `text`.
+    last: usize,
+}
+
+/// Convert pinyin typed without tones to Chinese, see [the module](self).
+pub struct Ime<M> {
+    model: M,
+    /// Ids of syllables as typed, `ü` is typed as `v`.
+    syllable_ids: BTreeMap<String, usize>,
+    /// Chars of each syllable by ids, the most likely first. Chars unknown by the model are
+    /// in the order of code points.
+    chars: Vec<Vec<String>>,
+    words: Vec<Word>,
+    /// Indexes of `words` by ids of their first syllable.
+    words_by_first: HashMap<usize, Vec<usize>>,
+}
+
+impl<M: LanguageModel> Ime<M> {
+    /// Chars come from `db` by its [`ReverseIndex`], words come from phrases of `db` and
+    /// words of `model`.
+    pub fn new(db: &DB, model: M) -> Self {
+        let index = ReverseIndex::new(db);
+        let mut ime = Self {
+            model,
+            syllable_ids: BTreeMap::new(),
+            chars: vec![],
+            words: vec![],
+            words_by_first: HashMap::new(),
+        };
+        let mut ids: HashMap<u16, usize> = HashMap::new();
+        for p in index.syllables() {
+            let Some(text) = p.typed() else { continue };
+            let mut chars: Vec<String> = index
+                .chars_toneless(p)
+                .iter()
+                .map(char::to_string)
+                .collect();
+            // stable sort keeps the order of code points for chars of the same score
+            let model = &ime.model;
+            chars.sort_by(|a, b| model.word(b).total_cmp(&model.word(a)));
+            ids.insert(p.into(), ime.chars.len());
+            ime.syllable_ids.insert(text, ime.chars.len());
+            ime.chars.push(chars);
+        }
+
+        let id = |p: Pinyin| ids.get(&p.toneless().into()).copied();
+        let mut seen = HashSet::new();
+        let phrases = db.phrases().map(|(text, readings)| {
+            let syllables = readings.iter().map(|&p| id(p).into_iter().collect());
+            (text.to_string(), syllables.collect::<Vec<Vec<usize>>>())
+        });
+        let model_words: Vec<(String, Vec<Vec<usize>>)> = ime
+            .model
+            .words()
+            .into_iter()
+            .map(|text| {
+                let syllables = text.chars().map(|c| {
+                    db.polyphone(c)
+                        .map(|r| r.iter().filter_map(id).collect())
+                        .unwrap_or_default()
+                });
+                (text.to_string(), syllables.collect())
+            })
+            .collect();
+        for (text, syllables) in phrases.chain(model_words) {
+            if syllables.len() < 2 || syllables.iter().any(Vec::is_empty) {
+                continue;
+            }
+            if !seen.insert(text.clone()) {
+                continue;
+            }
+            for &first in &syllables[0] {
+                ime.words_by_first
+                    .entry(first)
+                    .or_default()
+                    .push(ime.words.len());
+            }
+            ime.words.push(Word { text, syllables });
+        }
+        ime
+    }
+
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Top `n` conversions of `input`, the best first. Letters are case insensitive, return
+    /// nothing if `input` has other chars than ASCII letters and apostrophes, or no
+    /// conversion covers the whole input.
+    pub fn convert(&self, input: &str, n: usize) -> Vec<Candidate> {
+        let input = input.to_ascii_lowercase();
+        let has_syllable = input.bytes().any(|b| b != b'\'');
+        if !has_syllable || !input.bytes().all(|b| b.is_ascii_lowercase() || b == b'\'') {
+            return vec![];
+        }
+        let segments: Vec<Vec<Segment>> = (0..input.len())
+            .map(|start| self.segments(&input, start))
+            .collect();
+
+        let beam = n.max(BEAM);
+        let mut paths: Vec<Vec<Path>> = (0..=input.len()).map(|_| vec![]).collect();
+        paths[0].push(Path {
+            score: 0.0,
+            text: String::new(),
+            last: 0,
+        });
+        for pos in 0..input.len() {
+            let mut current = std::mem::take(&mut paths[pos]);
+            if current.is_empty() {
+                continue;
+            }
+            prune(&mut current, beam);
+            if input.as_bytes()[pos] == b'\'' {
+                paths[pos + 1].extend(current);
+                continue;
+            }
+            for edge in self.edges(input.as_bytes(), &segments, pos) {
+                for path in &current {
+                    let score = if path.text.is_empty() {
+                        self.model.word(edge.text)
+                    } else {
+                        self.model.next(&path.text[path.last..], edge.text)
+                    };
+                    paths[edge.end].push(Path {
+                        score: path.score + score + PARTIAL_PENALTY * edge.partials as f64,
+                        text: path.text.clone() + edge.text,
+                        last: path.text.len(),
+                    });
+                }
+            }
+        }
+
+        let mut result = std::mem::take(&mut paths[input.len()]);
+        prune(&mut result, n);
+        result
+            .into_iter()
+            .map(|p| Candidate {
+                text: p.text,
+                score: p.score,
+            })
+            .collect()
+    }
+
+    /// Syllables that start at `start` of `input`. A syllable is partial if the text is
+    /// only a prefix of syllables, a complete syllable at the end of input is also partial
+    /// for syllables it is a prefix of, as the user may be still typing.
+    fn segments(&self, input: &str, start: usize) -> Vec<Segment> {
+        let mut result = vec![];
+        for end in start + 1..=input.len().min(start + MAX_SYLLABLE_LEN) {
+            let text = &input[start..end];
+            if text.ends_with('\'') {
+                break;
+            }
+            let full = self.syllable_ids.get(text).copied();
+            if let Some(id) = full {
+                result.push(Segment {
+                    end,
+                    syllables: vec![id],
+                    partial: false,
+                });
+            }
+            if full.is_none() || end == input.len() {
+                let prefixed: Vec<usize> = self
+                    .syllable_ids
+                    .range(text.to_string()..)
+                    .take_while(|(s, _)| s.starts_with(text))
+                    .filter(|(s, _)| s.len() > text.len())
+                    .map(|(_, &id)| id)
+                    .collect();
+                if !prefixed.is_empty() {
+                    result.push(Segment {
+                        end,
+                        syllables: prefixed,
+                        partial: true,
+                    });
+                }
+            }
+        }
+        result
+    }
+
+    /// Chars and words that start at `pos`.
+    fn edges<'a>(&'a self, input: &[u8], segments: &[Vec<Segment>], pos: usize) -> Vec<Edge<'a>> {
+        let mut edges = vec![];
+        for seg in &segments[pos] {
+            let partials = seg.partial as usize;
+            let mut chars: Vec<&String> = seg
+                .syllables
+                .iter()
+                .flat_map(|&id| self.chars[id].iter().take(CHARS_PER_SYLLABLE))
+                .collect();
+            if seg.syllables.len() > 1 {
+                chars.sort_by(|a, b| self.model.word(b).total_cmp(&self.model.word(a)));
+                chars.dedup();
+                chars.truncate(CHARS_PER_SYLLABLE);
+            }
+            edges.extend(chars.into_iter().map(|c| Edge {
+                end: seg.end,
+                text: c,
+                partials,
+            }));
+
+            let mut words: Vec<usize> = seg
+                .syllables
+                .iter()
+                .filter_map(|id| self.words_by_first.get(id))
+                .flatten()
+                .copied()
+                .collect();
+            words.sort_unstable();
+            words.dedup();
+            for word in words.into_iter().map(|i| &self.words[i]) {
+                let mut ends = vec![];
+                let syllables = &word.syllables[1..];
+                self.match_word(input, segments, syllables, seg.end, partials, &mut ends);
+                edges.extend(ends.into_iter().map(|(end, partials)| Edge {
+                    end,
+                    text: &word.text,
+                    partials,
+                }));
+            }
+        }
+        edges
+    }
+
+    /// Find ends of input positions where `syllables` match segments from `pos`, with the
+    /// number of partial syllables. Apostrophes between syllables are skipped.
+    fn match_word(
+        &self,
+        input: &[u8],
+        segments: &[Vec<Segment>],
+        syllables: &[Vec<usize>],
+        pos: usize,
+        partials: usize,
+        ends: &mut Vec<(usize, usize)>,
+    ) {
+        let Some((first, rest)) = syllables.split_first() else {
+            ends.push((pos, partials));
+            return;
+        };
+        if input.get(pos) == Some(&b'\'') {
+            return self.match_word(input, segments, syllables, pos + 1, partials, ends);
+        }
+        let Some(segs) = segments.get(pos) else {
+            return;
+        };
+        for seg in segs {
+            if seg.syllables.iter().any(|id| first.contains(id)) {
+                let partials = partials + seg.partial as usize;
+                self.match_word(input, segments, rest, seg.end, partials, ends);
+            }
+        }
+    }
+}
+
+/// Keep the best `n` paths, and the best one of paths of the same text.
+fn prune(paths: &mut Vec<Path>, n: usize) {
+    paths.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut seen = HashSet::new();
+    paths.retain(|p| seen.insert(p.text.clone()));
+    paths.truncate(n);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    const DATA: &str = "\
+U+4E2D: zhōng,zhòng  # 中
+U+949F: zhōng  # 钟
+U+56FD: guó  # 国
+U+679C: guǒ  # 果
+U+4EBA: rén  # 人
+U+4EC1: rén  # 仁
+U+897F: xī  # 西
+U+5B89: ān  # 安
+U+5148: xiān  # 先
+U+60F3: xiǎng  # 想
+U+5973: nǚ  # 女
+";
+
+    const MODEL: &str = "\
+# word count
+中 100
+国 80
+人 90
+钟 10
+果 20
+西 30
+安 30
+先 50
+想 60
+西安 20
+中国 50
+国人 5
+中国 人 20
+";
+
+    fn ime() -> Ime<NgramModel> {
+        let mut db = DB::load(DATA).unwrap();
+        db.load_phrases("中国人: zhōng guó rén\n").unwrap();
+        Ime::new(&db, NgramModel::load(MODEL).unwrap())
+    }
+
+    fn texts(input: &str, n: usize) -> Vec<String> {
+        ime()
+            .convert(input, n)
+            .into_iter()
+            .map(|c| c.text)
+            .collect()
+    }
+
+    #[rstest]
+    #[case("zhongguoren", "中国人")]
+    #[case("zgr", "中国人")]
+    #[case("ZhongGuo", "中国")]
+    #[case("zhongg", "中国")]
+    #[case("xian", "先")]
+    #[case("xi'an", "西安")]
+    #[case("nv", "女")]
+    fn best(#[case] input: &str, #[case] exp: &str) {
+        assert_eq!(texts(input, 1), [exp]);
+    }
+
+    #[test]
+    fn ranked() {
+        let candidates = ime().convert("zhongguo", 4);
+        let texts: Vec<&str> = candidates.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts[0], "中国");
+        assert!(texts.contains(&"中果"));
+        assert!(candidates.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(candidates.len() <= 4);
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("zhong1")]
+    #[case("中")]
+    #[case("q")]
+    #[case("'")]
+    #[case("''")]
+    fn no_conversion(#[case] input: &str) {
+        assert!(texts(input, 5).is_empty());
+    }
+
+    #[test]
+    fn bigram() {
+        // 人 follows 中国 more likely than 仁
+        let model = NgramModel::load(MODEL).unwrap();
+        assert!(model.next("中国", "人") > model.next("中国", "仁"));
+        assert!(model.next("中国", "人") > model.word("人"));
+    }
+
+    #[test]
+    fn load_error() {
+        let e = NgramModel::load("中国 50\n中国 many\n").err().unwrap();
+        assert_eq!(e.line, 2);
+        assert_eq!(
+            e.to_string(),
+            "line 2: expected `word count` or `word word count` in '中国 many'"
+        );
+    }
+}
@@ -0,0 +1,681 @@
+//! Prefix search over many Chinese strings by their pinyin, such as product names.
+use crate::Pinyinizer;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::ops::Deref;
+use std::sync::Arc;
+
+/// Max keys of each kind for a string, keys reading fewer chars by other than their most
+/// common reading are kept first.
+const MAX_ALTERNATIVES: usize = 4;
+/// Bytes of a key in the tree, strings found by the first bytes of a longer query are
+/// checked against their whole keys.
+const MAX_KEY_LEN: usize = 16;
+
+/// How a key spells the string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum KeyKind {
+    /// Complete syllables, such as `zhongguo` for `中国`.
+    Full,
+    /// First letters of syllables, such as `zg` for `中国`.
+    Initials,
+}
+
+/// Order of a string found by a key, the shorter key, complete syllables and the smaller id
+/// first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Rank {
+    /// Length of the whole key.
+    len: u32,
+    kind: KeyKind,
+    id: u64,
+}
+
+/// Bytes of keys from a node to its child, kept in the node as keys are short.
+#[derive(Debug, Clone, Copy, Default)]
+struct Label {
+    len: u8,
+    bytes: [u8; MAX_KEY_LEN],
+}
+
+impl Label {
+    fn new(s: &[u8]) -> Self {
+        let mut bytes = [0; MAX_KEY_LEN];
+        bytes[..s.len()].copy_from_slice(s);
+        Self {
+            len: s.len() as u8,
+            bytes,
+        }
+    }
+}
+
+impl Deref for Label {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes[..self.len as usize]
+    }
+}
+
+/// Strings whose key ends at a node. Most keys belong to one string, whose posting is kept
+/// in the node; more postings are behind a thin pointer, so that the node does not grow.
+#[derive(Debug, Clone, Default)]
+enum Postings {
+    #[default]
+    Empty,
+    One(Rank),
+    Many(Box<Vec<Rank>>),
+}
+
+impl Postings {
+    /// Change postings as a vector.
+    fn edit(&mut self, f: impl FnOnce(&mut Vec<Rank>)) {
+        let mut v = match std::mem::take(self) {
+            Postings::Empty => vec![],
+            Postings::One(rank) => vec![rank],
+            Postings::Many(v) => *v,
+        };
+        f(&mut v);
+        *self = match v[..] {
+            [] => Postings::Empty,
+            [rank] => Postings::One(rank),
+            _ => Postings::Many(Box::new(v)),
+        };
+    }
+}
+
+impl Deref for Postings {
+    type Target = [Rank];
+
+    fn deref(&self) -> &[Rank] {
+        match self {
+            Postings::Empty => &[],
+            Postings::One(rank) => std::slice::from_ref(rank),
+            Postings::Many(v) => v,
+        }
+    }
+}
+
+/// Slices are boxed rather than vectors to save memory of the many nodes.
+#[derive(Debug, Clone, Default)]
+struct Node {
+    /// Bytes of keys from the parent to this node, empty for the root.
+    label: Label,
+    parent: u32,
+    /// Sorted by the first byte of their labels.
+    children: Box<[u32]>,
+    /// Strings whose key ends at this node, sorted.
+    postings: Postings,
+    /// Best rank of postings in the subtree, `None` if there is none.
+    best: Option<Rank>,
+}
+
+/// A string and the nodes of its keys.
+#[derive(Debug, Clone)]
+struct Entry {
+    text: Box<str>,
+    nodes: Box<[u32]>,
+}
+
+/// Item of the search queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Item {
+    /// A posting of a node by its position.
+    Posting(u32, u32),
+    /// The subtree of a node.
+    Node(u32),
+}
+
+/// Index of strings by their pinyin for prefix and abbreviation queries, such as `zhong`,
+/// `zhongguoyin` or `zgyh` for `中国银行`.
+///
+/// Each string is indexed by its complete syllables and by the first letters of syllables,
+/// with keys of polyphone alternatives. ASCII letters and digits are kept as is, other chars
+/// without reading are ignored. Keys are stored in a radix tree whose nodes know the best
+/// string below them, so a search visits about as many nodes as the strings it returns.
+/// Strings can be inserted and deleted at any time, freed nodes are reused.
+///
+/// 2 million product names of 4 to 11 chars take about 1.5 GiB, as measured and printed by
+/// `benches/index.rs`.
+pub struct PinyinIndex {
+    pinyinizer: Arc<Pinyinizer>,
+    /// The root is the first node.
+    nodes: Vec<Node>,
+    /// Nodes removed from the tree, to be reused.
+    free: Vec<u32>,
+    entries: HashMap<u64, Entry>,
+}
+
+impl PinyinIndex {
+    /// Read strings by the default [`Pinyinizer`] with the embedded pinyin data.
+    #[cfg(feature = "embedded-data")]
+    pub fn new() -> Self {
+        Self::with_pinyinizer(crate::default_pinyinizer())
+    }
+
+    pub fn with_pinyinizer(pinyinizer: Arc<Pinyinizer>) -> Self {
+        Self {
+            pinyinizer,
+            nodes: vec![Node::default()],
+            free: vec![],
+            entries: HashMap::new(),
+        }
+    }
+
+    /// Number of strings.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The string of `id`.
+    pub fn get(&self, id: u64) -> Option<&str> {
+        self.entries.get(&id).map(|e| &*e.text)
+    }
+
+    /// Index `text` by `id`, replace the previous string of `id`.
+    ///
+    /// Polyphone chars make at most 4 keys of each kind: the most common readings, then keys
+    /// with one char read otherwise, from the start of the string, then keys with two. So
+    /// two polyphone chars of two readings are found by any of their readings, but of
+    /// `长行乐重`, only one of the first three chars can be read otherwise and `重` only as
+    /// `zhong`.
+    pub fn insert(&mut self, id: u64, text: &str) {
+        self.delete(id);
+        let mut nodes = vec![];
+        for (key, kind) in self.keys(text) {
+            let rank = Rank {
+                len: key.len() as u32,
+                kind,
+                id,
+            };
+            let node = self.node_of(&key.as_bytes()[..key.len().min(MAX_KEY_LEN)]);
+            // keys of the same first bytes share a node
+            let postings = &mut self.nodes[node].postings;
+            match postings.iter().position(|p| p.id == id) {
+                Some(i) if postings[i] <= rank => continue,
+                Some(i) => postings.edit(|v| {
+                    v.remove(i);
+                }),
+                None => nodes.push(node as u32),
+            }
+            postings.edit(|v| {
+                let i = v.partition_point(|p| *p < rank);
+                v.insert(i, rank);
+            });
+            self.promote(node, rank);
+        }
+        let entry = Entry {
+            text: text.into(),
+            nodes: nodes.into(),
+        };
+        self.entries.insert(id, entry);
+    }
+
+    /// Remove the string of `id`, return it if exists.
+    pub fn delete(&mut self, id: u64) -> Option<String> {
+        let entry = self.entries.remove(&id)?;
+        for &node in entry.nodes.iter() {
+            let node = node as usize;
+            self.nodes[node].postings.edit(|v| v.retain(|p| p.id != id));
+            self.update(node);
+        }
+        Some(entry.text.into())
+    }
+
+    /// Ids of strings whose complete syllables or first letters start with `query`, at
+    /// most `limit`. Letters are case insensitive, chars other than ASCII letters and digits
+    /// are ignored.
+    ///
+    /// Polyphone chars are matched by the readings kept by [`PinyinIndex::insert`].
+    ///
+    /// Strings spelled exactly by `query` come first, then strings of shorter keys, complete
+    /// syllables before first letters of the same length, and the smaller id first.
+    pub fn search(&self, query: &str, limit: usize) -> Vec<u64> {
+        let query: Vec<u8> = query
+            .bytes()
+            .filter(u8::is_ascii_alphanumeric)
+            .map(|b| b.to_ascii_lowercase())
+            .collect();
+        if query.is_empty() || limit == 0 {
+            return vec![];
+        }
+        let Some(root) = self.find(&query[..query.len().min(MAX_KEY_LEN)]) else {
+            return vec![];
+        };
+
+        // visit subtrees and postings by their best ranks
+        let mut result = vec![];
+        let mut seen = HashSet::new();
+        let mut queue = BinaryHeap::new();
+        if let Some(best) = self.nodes[root].best {
+            queue.push(Reverse((best, Item::Node(root as u32))));
+        }
+        while let Some(Reverse((rank, item))) = queue.pop() {
+            match item {
+                Item::Node(i) => {
+                    let node = &self.nodes[i as usize];
+                    if let Some(&p) = node.postings.first() {
+                        queue.push(Reverse((p, Item::Posting(i, 0))));
+                    }
+                    for &c in node.children.iter() {
+                        if let Some(best) = self.nodes[c as usize].best {
+                            queue.push(Reverse((best, Item::Node(c))));
+                        }
+                    }
+                }
+                Item::Posting(i, j) => {
+                    if let Some(&p) = self.nodes[i as usize].postings.get(j as usize + 1) {
+                        queue.push(Reverse((p, Item::Posting(i, j + 1))));
+                    }
+                    if seen.insert(rank.id)
+                        && (query.len() <= MAX_KEY_LEN || self.has_key(rank.id, &query))
+                    {
+                        result.push(rank.id);
+                        if result.len() == limit {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        result
+    }
+
+    /// Whether a whole key of the string of `id` starts with `query`.
+    fn has_key(&self, id: u64, query: &[u8]) -> bool {
+        self.entries.get(&id).is_some_and(|e| {
+            self.keys(&e.text)
+                .iter()
+                .any(|(key, _)| key.as_bytes().starts_with(query))
+        })
+    }
+
+    /// Position of the child of `i` whose label starts with `b`.
+    fn child(&self, i: usize, b: u8) -> Result<usize, usize> {
+        self.nodes[i]
+            .children
+            .binary_search_by_key(&b, |&c| self.nodes[c as usize].label[0])
+    }
+
+    /// The node whose subtree has all keys starting with `key`.
+    fn find(&self, mut key: &[u8]) -> Option<usize> {
+        let mut i = 0;
+        while !key.is_empty() {
+            let c = self.nodes[i].children[self.child(i, key[0]).ok()?] as usize;
+            let label = &self.nodes[c].label;
+            let len = common_prefix(label, key);
+            if len == key.len() {
+                return Some(c);
+            }
+            if len < label.len() {
+                return None;
+            }
+            i = c;
+            key = &key[len..];
+        }
+        Some(i)
+    }
+
+    fn alloc(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(i) => {
+                self.nodes[i as usize] = node;
+                i as usize
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    /// Node of `key`, created if not exists.
+    fn node_of(&mut self, mut key: &[u8]) -> usize {
+        let mut i = 0;
+        while !key.is_empty() {
+            let pos = match self.child(i, key[0]) {
+                Ok(pos) => pos,
+                Err(pos) => {
+                    let leaf = self.alloc(Node {
+                        label: Label::new(key),
+                        parent: i as u32,
+                        ..Node::default()
+                    });
+                    edit(&mut self.nodes[i].children, |v| v.insert(pos, leaf as u32));
+                    return leaf;
+                }
+            };
+            let c = self.nodes[i].children[pos] as usize;
+            let label = self.nodes[c].label;
+            let len = common_prefix(&label, key);
+            if len < label.len() {
+                // split the label, the first part becomes a new node
+                let (head, tail) = (Label::new(&label[..len]), Label::new(&label[len..]));
+                let mid = self.alloc(Node {
+                    label: head,
+                    parent: i as u32,
+                    children: Box::new([c as u32]),
+                    postings: Postings::Empty,
+                    best: self.nodes[c].best,
+                });
+                self.nodes[c].label = tail;
+                self.nodes[c].parent = mid as u32;
+                self.nodes[i].children[pos] = mid as u32;
+                i = mid;
+            } else {
+                i = c;
+            }
+            key = &key[len..];
+        }
+        i
+    }
+
+    /// Update best ranks from `i` to the root after `rank` is added to `i`.
+    fn promote(&mut self, mut i: usize, rank: Rank) {
+        loop {
+            let node = &mut self.nodes[i];
+            if node.best.is_some_and(|best| best <= rank) {
+                return;
+            }
+            node.best = Some(rank);
+            if i == 0 {
+                return;
+            }
+            i = node.parent as usize;
+        }
+    }
+
+    /// Update nodes from `i` to the root after postings are removed from `i`. Nodes without
+    /// postings are freed if they have no child, or merged into their only child.
+    fn update(&mut self, mut i: usize) {
+        while i != 0 {
+            let parent = self.nodes[i].parent as usize;
+            let node = &self.nodes[i];
+            if node.postings.is_empty() && node.children.len() <= 1 {
+                let pos = self.child(parent, node.label[0]).unwrap();
+                let node = std::mem::take(&mut self.nodes[i]);
+                match node.children.first() {
+                    Some(&c) => {
+                        let child = &mut self.nodes[c as usize];
+                        child.label = Label::new(&[&node.label[..], &child.label[..]].concat());
+                        child.parent = parent as u32;
+                        self.nodes[parent].children[pos] = c;
+                    }
+                    None => edit(&mut self.nodes[parent].children, |v| {
+                        v.remove(pos);
+                    }),
+                }
+                self.free.push(i as u32);
+            } else {
+                self.rebest(i);
+            }
+            i = parent;
+        }
+        self.rebest(0);
+    }
+
+    /// Compute the best rank of `i` from its postings and children.
+    fn rebest(&mut self, i: usize) {
+        let node = &self.nodes[i];
+        let best = node
+            .children
+            .iter()
+            .filter_map(|&c| self.nodes[c as usize].best)
+            .chain(node.postings.first().copied())
+            .min();
+        self.nodes[i].best = best;
+    }
+
+    /// Keys of `text` of both kinds, without duplicates.
+    fn keys(&self, text: &str) -> Vec<(String, KeyKind)> {
+        let mut full = vec![];
+        let mut initials = vec![];
+        for c in text.chars() {
+            let alternatives: Vec<String> = if c.is_ascii_alphanumeric() {
+                vec![c.to_ascii_lowercase().to_string()]
+            } else {
+                let mut readings: Vec<String> = self
+                    .pinyinizer
+                    .char_readings(c)
+                    .into_iter()
+                    .filter_map(|p| p.typed())
+                    .collect();
+                // keep the most common reading first
+                let mut seen = HashSet::new();
+                readings.retain(|r| seen.insert(r.clone()));
+                readings
+            };
+            if alternatives.is_empty() {
+                continue;
+            }
+            let mut letters: Vec<String> =
+                alternatives.iter().map(|s| s[..1].to_string()).collect();
+            let mut seen = HashSet::new();
+            letters.retain(|l| seen.insert(l.clone()));
+            full.push(alternatives);
+            initials.push(letters);
+        }
+
+        let mut result = vec![];
+        let mut seen = HashSet::new();
+        let keys = spell(&full)
+            .into_iter()
+            .map(|k| (k, KeyKind::Full))
+            .chain(spell(&initials).into_iter().map(|k| (k, KeyKind::Initials)));
+        for (key, kind) in keys {
+            if !key.is_empty() && seen.insert(key.clone()) {
+                result.push((key, kind));
+            }
+        }
+        result
+    }
+}
+
+/// Change a boxed slice as a vector.
+fn edit<T>(slice: &mut Box<[T]>, f: impl FnOnce(&mut Vec<T>)) {
+    let mut v = std::mem::take(slice).into_vec();
+    f(&mut v);
+    *slice = v.into_boxed_slice();
+}
+
+fn common_prefix(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b).take_while(|(x, y)| x == y).count()
+}
+
+/// Keys spelled by one of the `alternatives` of each char, at most [`MAX_ALTERNATIVES`].
+/// Keys with fewer chars other than their first alternative come first, so that the keys
+/// are spread across the string rather than spent on combinations of its first chars.
+fn spell(alternatives: &[Vec<String>]) -> Vec<String> {
+    // choices of each char by breadth first search, with the char to change next
+    let mut choices = vec![(vec![0; alternatives.len()], 0)];
+    let mut i = 0;
+    while i < choices.len() && choices.len() < MAX_ALTERNATIVES {
+        let (choice, start) = choices[i].clone();
+        for (j, a) in alternatives.iter().enumerate().skip(start) {
+            for k in 1..a.len() {
+                let mut next = choice.clone();
+                next[j] = k;
+                choices.push((next, j + 1));
+            }
+        }
+        i += 1;
+    }
+    choices.truncate(MAX_ALTERNATIVES);
+    choices
+        .iter()
+        .map(|(choice, _)| {
+            choice
+                .iter()
+                .zip(alternatives)
+                .map(|(&k, a)| a[k].as_str())
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(feature = "embedded-data")]
+impl Default for PinyinIndex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn pinyinizer() -> Arc<Pinyinizer> {
+        let pinyinizer = Pinyinizer::from_data(
+            "U+4E2D: zhōng,zhòng\nU+56FD: guó\nU+94F6: yín\nU+884C: xíng,háng\n\
+            U+4EBA: rén\nU+6C11: mín\nU+5BB6: jiā\nU+7535: diàn\n\
+            U+957F: cháng,zhǎng\nU+4E50: lè,yuè\nU+91CD: zhòng,chóng\n",
+        )
+        .unwrap();
+        Arc::new(pinyinizer)
+    }
+
+    fn index() -> PinyinIndex {
+        let mut index = PinyinIndex::with_pinyinizer(pinyinizer());
+        index.insert(1, "中国银行");
+        index.insert(2, "中国人民银行");
+        index.insert(3, "中国");
+        index.insert(4, "家电 iPhone");
+        index
+    }
+
+    /// Nodes in the tree.
+    fn live_nodes(index: &PinyinIndex) -> usize {
+        index.nodes.len() - index.free.len()
+    }
+
+    #[rstest]
+    #[case("zhong", &[3, 1, 2])]
+    #[case("zhongguo", &[3, 1, 2])]
+    #[case("zg", &[3, 1, 2])]
+    #[case("zgyh", &[1])]
+    #[case("zhongguoyin", &[1])]
+    #[case("zg rm", &[2])]
+    #[case("ZGRMYH", &[2])]
+    #[case("jdiph", &[4])]
+    #[case("jiadianiphone", &[4])]
+    #[case("guo", &[])]
+    #[case("", &[])]
+    fn search(#[case] query: &str, #[case] exp: &[u64]) {
+        // `行` reads only `xíng` without polyphone readings
+        #[cfg(not(feature = "polyphone"))]
+        let exp: &[u64] = if query.to_lowercase().ends_with("yh") {
+            &[]
+        } else {
+            exp
+        };
+        assert_eq!(index().search(query, 10), exp);
+    }
+
+    #[test]
+    #[cfg(feature = "polyphone")]
+    fn polyphone() {
+        let index = index();
+        assert_eq!(index.search("zhongguoyinhang", 10), [1]);
+        assert_eq!(index.search("zhongguoyinxing", 10), [1]);
+        assert_eq!(index.search("zgyx", 10), [1]);
+    }
+
+    #[test]
+    #[cfg(feature = "polyphone")]
+    fn polyphone_limit() {
+        let mut index = PinyinIndex::with_pinyinizer(pinyinizer());
+        index.insert(1, "长行");
+        index.insert(2, "长行乐重");
+        // all combinations of two polyphone chars
+        assert_eq!(index.search("zhanghang", 10), [1]);
+        assert_eq!(index.search("zhangh", 10), [1]);
+        // one of the first three chars read otherwise
+        assert_eq!(index.search("zhangxingle", 10), [2]);
+        assert_eq!(index.search("changhangle", 10), [2]);
+        assert_eq!(index.search("changxingyue", 10), [2]);
+        assert_eq!(index.search("cxyz", 10), [2]);
+        // dropped: the last char read otherwise, two chars read otherwise
+        assert!(index.search("changxinglechong", 10).is_empty());
+        assert!(index.search("cxlc", 10).is_empty());
+        assert!(index.search("zhanghangle", 10).is_empty());
+        assert!(index.search("zhly", 10).is_empty());
+    }
+
+    #[test]
+    fn ranking_and_limit() {
+        let index = index();
+        // exact spelling first
+        assert_eq!(index.search("zg", 1), [3]);
+        assert_eq!(index.search("zhongguo", 2), [3, 1]);
+        assert!(index.search("zhong", 0).is_empty());
+    }
+
+    #[test]
+    fn long_query() {
+        let mut index = index();
+        index.insert(5, "中国人民银行中国人民银行");
+        let full = "zhongguorenminyinxingzhongguorenminyinxing";
+        assert_eq!(index.search(full, 10), [5]);
+        assert_eq!(index.search(&full[..35], 10), [5]);
+        assert!(index
+            .search("zhongguorenminyinxingzhongguorenmao", 10)
+            .is_empty());
+    }
+
+    #[test]
+    fn insert_and_delete() {
+        let mut index = index();
+        assert_eq!(index.len(), 4);
+        assert_eq!(index.delete(1).as_deref(), Some("中国银行"));
+        assert_eq!(index.delete(1), None);
+        assert_eq!(index.search("zg", 10), [3, 2]);
+
+        // replace the string of an id
+        index.insert(3, "人民");
+        assert_eq!(index.search("zg", 10), [2]);
+        assert_eq!(index.search("rm", 10), [3]);
+        assert_eq!(index.get(3), Some("人民"));
+        assert_eq!(index.len(), 3);
+    }
+
+    #[test]
+    fn delete_after_readings_change() {
+        let pinyinizer = pinyinizer();
+        let mut index = PinyinIndex::with_pinyinizer(pinyinizer.clone());
+        index.insert(1, "中国");
+        let mut dict = pinyinizer.user_dict().write().unwrap();
+        dict.insert_char('中', vec!["zhà".parse().unwrap()]);
+        drop(dict);
+        index.delete(1);
+        assert!(index.search("zg", 10).is_empty());
+        assert_eq!(live_nodes(&index), 1);
+    }
+
+    #[test]
+    fn reuse_nodes() {
+        let mut index = index();
+        let nodes = live_nodes(&index);
+        for _ in 0..3 {
+            for id in 10..20 {
+                index.insert(id, "人民银行");
+            }
+            for id in 10..20 {
+                index.delete(id);
+            }
+        }
+        assert_eq!(live_nodes(&index), nodes);
+        assert!(index.nodes.len() <= nodes + 8);
+        assert_eq!(index.search("zg", 10), [3, 1, 2]);
+
+        for id in 1..=4 {
+            index.delete(id);
+        }
+        assert_eq!(live_nodes(&index), 1);
+        assert_eq!(index.nodes[0].best, None);
+    }
+}
@@ -1,16 +1,64 @@
-mod db;
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
+#[cfg(feature = "std")]
+mod address;
+pub mod db;
+#[cfg(feature = "std")]
+pub mod ime;
+#[cfg(feature = "std")]
+mod index;
+#[cfg(feature = "std")]
+mod matcher;
+#[cfg(feature = "std")]
+mod name;
+mod number;
+mod phrase;
 mod pinyin;
-pub use crate::pinyin::Pinyin;
-use std::fmt::Write;
+#[cfg(feature = "std")]
+mod pinyinizer;
+#[cfg(feature = "std")]
+mod scan;
+mod syllable;
+#[cfg(feature = "std")]
+mod user_dict;
+#[cfg(feature = "std")]
+pub use crate::index::PinyinIndex;
+#[cfg(feature = "std")]
+pub use crate::matcher::FuzzyMatch;
+#[cfg(feature = "std")]
+pub use crate::name::{split_surname, NameCase, NameFormat, NameOrder};
+pub use crate::number::read_numbers;
+pub use crate::phrase::ReadingCountMismatch;
+pub use crate::pinyin::{Pinyin, PinyinDisplay, PinyinFromStrError};
+#[cfg(feature = "std")]
+pub use crate::pinyinizer::Pinyinizer;
+#[cfg(feature = "std")]
+pub use crate::user_dict::UserDict;
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+use std::sync::{Arc, RwLock};
 
+#[cfg(all(feature = "std", feature = "embedded-data"))]
 lazy_static::lazy_static! {
-    static ref DB: db::DB = {
-        db::DB::load(include_str!("pinyin.txt")).unwrap()
-    };
+    static ref DEFAULT: Arc<Pinyinizer> = Arc::new(Pinyinizer::embedded());
+}
+
+/// The default [`Pinyinizer`] with the embedded pinyin data, used by the free functions of
+/// this crate.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+pub fn default_pinyinizer() -> Arc<Pinyinizer> {
+    DEFAULT.clone()
+}
+
+/// User dictionary of the default [`Pinyinizer`], it takes precedence over the built-in data
+/// in all conversions.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+pub fn user_dict() -> &'static RwLock<UserDict> {
+    DEFAULT.user_dict()
 }
 
 /// How to represent the tone of a pinyin syllable.
-#[derive(Copy, Clone, PartialEq, Eq)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
 #[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
 #[cfg_attr(feature = "serde", derive(serde::Deserialize))]
 pub enum ToneRepresentation {
@@ -26,44 +74,74 @@ impl Default for ToneRepresentation {
 }
 
 /// Return pinyin of a Chinese characters separated by space.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
 pub fn pinyin(s: &str, tone_repr: ToneRepresentation) -> String {
-    let mut result = String::new();
-    for c in s.chars() {
-        if let Some(pinyin) = DB.get(c) {
-            let repr = match tone_repr {
-                ToneRepresentation::None => pinyin::PinyinDisplay::NoTones(pinyin.into()),
-                ToneRepresentation::Numbered => pinyin::PinyinDisplay::NumberedTone(pinyin.into()),
-                ToneRepresentation::Unicode => pinyin::PinyinDisplay::UnicodeTone(pinyin.into()),
-            };
-            write!(&mut result, "{}", repr).unwrap();
-        } else {
-            result.push(c);
-        }
-        result.push(' ');
-    }
-    result
+    DEFAULT.pinyin(s, tone_repr)
+}
+
+/// Write the result of [`pinyin`] to `w` without allocation, such as a reused `String`.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+pub fn pinyin_into(
+    s: &str,
+    tone_repr: ToneRepresentation,
+    w: &mut impl core::fmt::Write,
+) -> core::fmt::Result {
+    DEFAULT.pinyin_into(s, tone_repr, w)
 }
 
 /// Replace Chinese characters with their first letter. Ignore non-printable characters.
 /// Non Chinese characters are kept as is.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
 pub fn first_letters(s: &str) -> String {
-    let mut result = String::new();
-    for c in s.chars() {
-        if let Some(pinyin) = DB.get(c) {
-            write!(
-                &mut result,
-                "{}",
-                pinyin::PinyinDisplay::FirstLetter(pinyin.into())
-            )
-            .unwrap();
-        } else {
-            result.push(c);
-        }
-    }
-    result
+    DEFAULT.first_letters(s)
+}
+
+/// Write the result of [`first_letters`] to `w` without allocation.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+pub fn first_letters_into(s: &str, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+    DEFAULT.first_letters_into(s, w)
+}
+
+/// Match `query` typed in a search box against Chinese `text` by pinyin, such as
+/// `zhongguo`, `zg`, `zhong g` or `中g` for `中国银行`. Each char of `text` matches a complete
+/// or partial syllable of any of its readings, or itself. Chars of `text` may be skipped,
+/// spaces in `query` only separate syllables. Return the score and the matched chars for
+/// highlighting, `None` if it does not match.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+pub fn fuzzy_match(query: &str, text: &str) -> Option<FuzzyMatch> {
+    DEFAULT.fuzzy_match(query, text)
+}
+
+/// Romanize a Chinese personal name, such as `张三丰` to `Zhang Sanfeng`.
+///
+/// The surname is read by surname reading table, such as `单` reads `Shàn`. Syllables of
+/// surname and given name are joined as one word each.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+pub fn romanize_name(name: &str, format: &NameFormat) -> String {
+    DEFAULT.romanize_name(name, format)
+}
+
+/// Romanize a place name as one word, such as `六安` to `Lù'ān`.
+///
+/// Known place names are read by the place name dictionary, other characters are read by
+/// their most common reading.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+pub fn romanize_place(name: &str, tone_repr: ToneRepresentation) -> String {
+    DEFAULT.romanize_place(name, tone_repr)
+}
+
+/// Romanize a Chinese address in the conventional order, from the smallest unit to the
+/// largest, such as `北京市朝阳区建国路88号` to `Jianguo Lu 88 Hao, Chaoyang Qu, Beijing Shi`.
+///
+/// The street, house number and everything after them are kept in the original order as the
+/// first part, administrative divisions follow it in reverse order, separated by comma.
+#[cfg(all(feature = "std", feature = "embedded-data"))]
+pub fn romanize_address(address: &str, tone_repr: ToneRepresentation) -> String {
+    DEFAULT.romanize_address(address, tone_repr)
 }
 
 #[cfg(test)]
+#[cfg(all(feature = "std", feature = "embedded-data"))]
 mod tests {
     use super::*;
 
@@ -74,8 +152,59 @@ mod tests {
         assert_eq!(pinyin("你好", ToneRepresentation::Unicode), "nǐ hǎo ");
     }
 
+    #[test]
+    fn test_pinyin_read_numbers() {
+        assert_eq!(
+            pinyin(&read_numbers("2023年"), ToneRepresentation::Unicode),
+            "èr líng èr sān nián "
+        );
+        assert_eq!(
+            pinyin(&read_numbers("110"), ToneRepresentation::Numbered),
+            "yi1 bai3 yi1 shi2 "
+        );
+    }
+
+    #[test]
+    fn test_into() {
+        let mut buf = String::with_capacity(64);
+        pinyin_into("你好", ToneRepresentation::Numbered, &mut buf).unwrap();
+        first_letters_into("你l好", &mut buf).unwrap();
+        assert_eq!(buf, "ni3 hao3 nlh");
+    }
+
+    #[test]
+    fn test_fuzzy_match() {
+        // `行` reads `háng` only with polyphone readings
+        #[cfg(feature = "polyphone")]
+        {
+            let m = fuzzy_match("zgyh", "中国银行").unwrap();
+            assert_eq!(m.ranges.first(), Some(&(0..12)));
+            assert_eq!(m.ranges.len(), 1);
+        }
+        assert!(fuzzy_match("zgyh", "中国人").is_none());
+    }
+
     #[test]
     fn test_first_letters() {
         assert_eq!(first_letters("你l好"), "nlh");
     }
+
+    #[test]
+    fn test_user_dict() {
+        // use chars not used by other tests, user dictionary is global, and chars of
+        // level-1 GB2312 to be embedded by all `coverage-*` features
+        let mut dict = user_dict().write().unwrap();
+        dict.load("U+5C4C: diǎo\n屌丝: diǎo sī").unwrap();
+        dict.insert_char('蝗', vec!["hā".parse().unwrap()]);
+        drop(dict);
+
+        assert_eq!(pinyin("屌丝蝗", ToneRepresentation::None), "diao si ha ");
+        assert_eq!(first_letters("屌"), "d");
+        assert_eq!(DEFAULT.char_reading('蝗'), Some("hā".parse().unwrap()));
+
+        let mut dict = user_dict().write().unwrap();
+        dict.remove_char('蝗');
+        drop(dict);
+        assert_eq!(pinyin("蝗", ToneRepresentation::None), "huang ");
+    }
 }
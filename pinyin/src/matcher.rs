@@ -0,0 +1,253 @@
+//! Match what users type in a search box against Chinese text, such as `zhongguo`, `zg`,
+//! `zhong g` or `中g` for `中国银行`.
+use crate::Pinyinizer;
+use std::ops::Range;
+use std::rc::Rc;
+
+/// Score of a complete syllable, a Han char or another char typed as is.
+const FULL: i32 = 4;
+/// Score of a syllable typed partially, more than its initial letter.
+const PARTIAL: i32 = 2;
+/// Score of the first letter of a syllable.
+const INITIAL: i32 = 1;
+/// Bonus of a char matched right after the previous matched char.
+const ADJACENT: i32 = 1;
+/// Bonus of a match starts at the first char.
+const AT_START: i32 = 2;
+/// Penalty of each char skipped between matched chars.
+const GAP: i32 = -1;
+
+/// Result of [`Pinyinizer::fuzzy_match`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Higher is better, complete syllables score higher than initials, contiguous matches
+    /// higher than scattered ones.
+    pub score: i32,
+    /// Byte ranges of matched chars in the text, adjacent chars are merged, for
+    /// highlighting.
+    pub ranges: Vec<Range<usize>>,
+}
+
+/// A char of the text to match.
+struct Target {
+    /// Byte range of the char.
+    range: Range<usize>,
+    /// The char in lower case, matched literally.
+    lower: char,
+    /// Readings as typed, all polyphone readings.
+    readings: Vec<String>,
+}
+
+impl Target {
+    fn new(p: &Pinyinizer, i: usize, c: char) -> Self {
+        let mut readings: Vec<String> = p
+            .char_readings(c)
+            .into_iter()
+            .filter_map(|r| r.typed())
+            .collect();
+        readings.sort_unstable();
+        readings.dedup();
+        Self {
+            range: i..i + c.len_utf8(),
+            lower: c.to_lowercase().next().unwrap_or(c),
+            readings,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    NotStarted,
+    Adjacent,
+    Gap,
+}
+
+const STATES: [State; 3] = [State::NotStarted, State::Adjacent, State::Gap];
+
+/// Chars matched by the rest of a match, shared by matches of the same rest.
+struct Path {
+    range: Range<usize>,
+    next: Option<Rc<Path>>,
+}
+
+/// Best match of the rest of the query from a char and a state.
+#[derive(Clone)]
+struct Cell {
+    score: i32,
+    path: Option<Rc<Path>>,
+}
+
+/// Cells of a char by query byte and state, `None` if the rest of the query does not match.
+type Row = Vec<[Option<Cell>; 3]>;
+
+/// Query bytes after `qi` and following spaces.
+fn skip_spaces(query: &str, qi: usize) -> usize {
+    qi + query[qi..].len() - query[qi..].trim_start_matches(' ').len()
+}
+
+/// Ways the target char matches the query at `qi`, by the end of consumed query and the
+/// score.
+fn consume(query: &str, qi: usize, t: &Target) -> Vec<(usize, i32)> {
+    let rest = &query[qi..];
+    let mut result = vec![];
+    if rest.starts_with(t.lower) {
+        result.push((qi + t.lower.len_utf8(), FULL));
+    }
+    for reading in &t.readings {
+        for len in 1..=reading.len() {
+            if rest.as_bytes().get(..len) == Some(&reading.as_bytes()[..len]) {
+                let score = match len {
+                    _ if len == reading.len() => FULL,
+                    1 => INITIAL,
+                    _ => PARTIAL,
+                };
+                result.push((qi + len, score));
+            }
+        }
+    }
+    result
+}
+
+/// Fill cells of the char `t` from cells of the next char.
+fn fill(query: &str, t: &Target, next: &Row, row: &mut Row) {
+    for qi in 0..query.len() {
+        if !query.is_char_boundary(qi) {
+            continue;
+        }
+        let matches = consume(query, qi, t);
+        for state in STATES {
+            let mut best = match state {
+                State::NotStarted => next[qi][State::NotStarted as usize].clone(),
+                _ => next[qi][State::Gap as usize].as_ref().map(|c| Cell {
+                    score: c.score + GAP,
+                    path: c.path.clone(),
+                }),
+            };
+            let bonus = match state {
+                State::NotStarted if t.range.start == 0 => AT_START,
+                State::Adjacent => ADJACENT,
+                _ => 0,
+            };
+            for &(end, score) in &matches {
+                let Some(rest) = &next[skip_spaces(query, end)][State::Adjacent as usize] else {
+                    continue;
+                };
+                let score = score + bonus + rest.score;
+                if best.as_ref().is_none_or(|b| score > b.score) {
+                    let path = Path {
+                        range: t.range.clone(),
+                        next: rest.path.clone(),
+                    };
+                    best = Some(Cell {
+                        score,
+                        path: Some(Rc::new(path)),
+                    });
+                }
+            }
+            row[qi][state as usize] = best;
+        }
+    }
+}
+
+/// See [`Pinyinizer::fuzzy_match`].
+///
+/// Best matches are computed from the last char of the text to the first one, keeping
+/// cells of only the current and the next char.
+pub(crate) fn fuzzy_match(p: &Pinyinizer, query: &str, text: &str) -> Option<FuzzyMatch> {
+    let query = query.trim_end().to_lowercase();
+    let start = skip_spaces(&query, 0);
+    if start == query.len() {
+        return None;
+    }
+    // the whole query matched, whatever the rest of the text is
+    let done = Some(Cell {
+        score: 0,
+        path: None,
+    });
+    let mut next: Row = vec![Default::default(); query.len() + 1];
+    next[query.len()] = [done.clone(), done.clone(), done];
+    let mut row = next.clone();
+    for (i, c) in text.char_indices().rev() {
+        fill(&query, &Target::new(p, i, c), &next, &mut row);
+        std::mem::swap(&mut next, &mut row);
+    }
+
+    let best = next[start][State::NotStarted as usize].take()?;
+    let mut ranges: Vec<Range<usize>> = vec![];
+    let mut path = best.path.as_deref();
+    while let Some(Path { range, next }) = path {
+        match ranges.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => ranges.push(range.clone()),
+        }
+        path = next.as_deref();
+    }
+    Some(FuzzyMatch {
+        score: best.score,
+        ranges,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn pinyinizer() -> Pinyinizer {
+        Pinyinizer::from_data(
+            "U+4E2D: zhōng,zhòng\nU+56FD: guó\nU+94F6: yín\nU+884C: xíng,háng\nU+5973: nǚ\n",
+        )
+        .unwrap()
+    }
+
+    /// Matched byte ranges as `(start, end)`.
+    fn ranges(query: &str, text: &str) -> Option<Vec<(usize, usize)>> {
+        let m = pinyinizer().fuzzy_match(query, text)?;
+        Some(m.ranges.into_iter().map(|r| (r.start, r.end)).collect())
+    }
+
+    #[rstest]
+    #[case("zhongguo", Some(vec![(0, 6)]))]
+    #[case("zg", Some(vec![(0, 6)]))]
+    #[case("zhong g", Some(vec![(0, 6)]))]
+    #[case("中g", Some(vec![(0, 6)]))]
+    #[case("ZhongGuo", Some(vec![(0, 6)]))]
+    #[case("yinhang", Some(vec![(6, 12)]))]
+    #[case("yh", Some(vec![(6, 12)]))]
+    #[case("zgh", Some(vec![(0, 6), (9, 12)]))]
+    #[case("zgx", Some(vec![(0, 6), (9, 12)]))]
+    #[case("guozhong", None)]
+    #[case("zhongguoren", None)]
+    #[case("", None)]
+    fn match_ranges(#[case] query: &str, #[case] exp: Option<Vec<(usize, usize)>>) {
+        #[cfg(not(feature = "polyphone"))]
+        let exp = exp.filter(|_| query != "yinhang" && query != "yh" && query != "zgh");
+        assert_eq!(ranges(query, "中国银行"), exp);
+    }
+
+    #[test]
+    fn literal_and_v() {
+        assert_eq!(ranges("t恤", "T恤"), Some(vec![(0, 4)]));
+        assert_eq!(ranges("nv", "女"), Some(vec![(0, 3)]));
+        assert_eq!(ranges("a1", "A1中"), Some(vec![(0, 2)]));
+    }
+
+    #[test]
+    fn scores() {
+        let p = pinyinizer();
+        let score = |q| p.fuzzy_match(q, "中国银行").unwrap().score;
+        assert!(score("zhongguo") > score("zhongg"));
+        assert!(score("zhongg") > score("zg"));
+        // contiguous and at start is better
+        assert!(score("zg") > score("zy"));
+        assert!(score("zg") > score("gy"));
+    }
+
+    #[test]
+    fn long_text() {
+        let text = "中".repeat(50_000) + "国";
+        assert_eq!(ranges("g", &text), Some(vec![(150_000, 150_003)]));
+        assert_eq!(ranges("zhonggu", &text), Some(vec![(149_997, 150_003)]));
+        assert_eq!(ranges("gz", &text), None);
+    }
+}
@@ -0,0 +1,181 @@
+//! Romanize Chinese personal names, following GB/T 28039-2011.
+use crate::pinyin::{capitalize, word};
+use crate::{Pinyin, Pinyinizer, ToneRepresentation};
+use std::collections::HashMap;
+
+/// Surnames that are compound, or read differently from the most common reading of the
+/// character.
+#[rustfmt::skip]
+const SURNAMES: &[(&str, &str)] = &[
+    // compound surnames
+    ("欧阳", "ōu yáng"), ("司马", "sī mǎ"), ("上官", "shàng guān"), ("诸葛", "zhū gě"),
+    ("东方", "dōng fāng"), ("皇甫", "huáng fǔ"), ("尉迟", "yù chí"), ("公孙", "gōng sūn"),
+    ("慕容", "mù róng"), ("长孙", "zhǎng sūn"), ("宇文", "yǔ wén"), ("司徒", "sī tú"),
+    ("夏侯", "xià hóu"), ("令狐", "líng hú"), ("澹台", "tán tái"), ("单于", "chán yú"),
+    ("轩辕", "xuān yuán"), ("端木", "duān mù"), ("呼延", "hū yán"), ("南宫", "nán gōng"),
+    ("万俟", "mò qí"), ("闻人", "wén rén"), ("东郭", "dōng guō"), ("西门", "xī mén"),
+    ("百里", "bǎi lǐ"), ("钟离", "zhōng lí"), ("申屠", "shēn tú"), ("公羊", "gōng yáng"),
+    ("赫连", "hè lián"), ("太史", "tài shǐ"), ("司空", "sī kōng"), ("淳于", "chún yú"),
+    ("濮阳", "pú yáng"), ("拓跋", "tuò bá"), ("第五", "dì wǔ"), ("仲孙", "zhòng sūn"),
+    ("宗政", "zōng zhèng"), ("公冶", "gōng yě"), ("段干", "duàn gān"), ("乐正", "yuè zhèng"),
+    // single surnames with special readings
+    ("单", "shàn"), ("曾", "zēng"), ("解", "xiè"), ("仇", "qiú"), ("区", "ōu"), ("查", "zhā"),
+    ("朴", "piáo"), ("盖", "gě"), ("缪", "miào"), ("尉", "yù"), ("覃", "qín"), ("繁", "pó"),
+    ("种", "chóng"), ("翟", "zhái"), ("员", "yùn"), ("乐", "yuè"), ("华", "huà"), ("任", "rén"),
+    ("纪", "jǐ"), ("燕", "yān"), ("宓", "fú"), ("秘", "bì"), ("召", "shào"), ("句", "gōu"),
+    ("折", "shé"), ("那", "nā"), ("祭", "zhài"), ("牟", "móu"), ("莘", "shēn"), ("隗", "wěi"),
+    ("阚", "kàn"), ("薄", "bó"), ("长", "cháng"), ("曲", "qū"), ("车", "chē"), ("郇", "huán"),
+    ("洗", "xiǎn"), ("眭", "suī"), ("过", "guō"), ("柏", "bǎi"), ("沈", "shěn"), ("褚", "chǔ"),
+    ("叶", "yè"), ("石", "shí"), ("藏", "zāng"), ("万", "wàn"), ("能", "nài"), ("重", "chóng"),
+];
+
+lazy_static::lazy_static! {
+    static ref SURNAME_TABLE: HashMap<&'static str, Vec<Pinyin>> = SURNAMES
+        .iter()
+        .map(|(name, reading)| {
+            let reading = reading.split(' ').map(|s| s.parse().unwrap()).collect();
+            (*name, reading)
+        })
+        .collect();
+}
+
+/// Order of surname and given name.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub enum NameOrder {
+    /// `Zhang Sanfeng`, the Chinese order.
+    #[default]
+    SurnameFirst,
+    /// `Sanfeng Zhang`
+    GivenNameFirst,
+}
+
+/// Letter case of the romanized name.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize))]
+pub enum NameCase {
+    /// `Zhang Sanfeng`
+    #[default]
+    Capitalized,
+    /// `ZHANG Sanfeng`, commonly used in passports and international documents.
+    UpperSurname,
+    /// `ZHANG SANFENG`
+    Upper,
+}
+
+/// How to format a romanized name.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct NameFormat {
+    pub tone_repr: ToneRepresentation,
+    pub order: NameOrder,
+    pub case: NameCase,
+}
+
+/// Names are written without tones by default.
+impl Default for NameFormat {
+    fn default() -> Self {
+        Self {
+            tone_repr: ToneRepresentation::None,
+            order: NameOrder::default(),
+            case: NameCase::default(),
+        }
+    }
+}
+
+/// Split a Chinese name into surname and given name. Compound surnames such as `欧阳` are
+/// recognized, otherwise the first character is the surname.
+pub fn split_surname(name: &str) -> (&str, &str) {
+    let compound = name.char_indices().nth(2).map_or(name.len(), |(i, _)| i);
+    if name[..compound].chars().count() == 2 && SURNAME_TABLE.contains_key(&name[..compound]) {
+        return name.split_at(compound);
+    }
+    let single = name.chars().next().map_or(0, char::len_utf8);
+    name.split_at(single)
+}
+
+pub(crate) fn romanize_name(p: &Pinyinizer, name: &str, format: &NameFormat) -> String {
+    let (surname, given_name) = split_surname(name);
+    let surname = match SURNAME_TABLE.get(surname) {
+        Some(reading) => word(reading.iter().copied().map(Ok), format.tone_repr),
+        None => word(name_chars(p, surname), format.tone_repr),
+    };
+    let given_name = word(name_chars(p, given_name), format.tone_repr);
+    let (surname, given_name) = match format.case {
+        NameCase::Capitalized => (capitalize(&surname), capitalize(&given_name)),
+        NameCase::UpperSurname => (surname.to_uppercase(), capitalize(&given_name)),
+        NameCase::Upper => (surname.to_uppercase(), given_name.to_uppercase()),
+    };
+
+    let (first, second) = match format.order {
+        NameOrder::SurnameFirst => (surname, given_name),
+        NameOrder::GivenNameFirst => (given_name, surname),
+    };
+    match (first.is_empty(), second.is_empty()) {
+        (_, true) => first,
+        (true, false) => second,
+        (false, false) => format!("{} {}", first, second),
+    }
+}
+
+/// Read each char of `s` by its most common reading, keep chars not in the database as is.
+fn name_chars<'a>(
+    p: &'a Pinyinizer,
+    s: &'a str,
+) -> impl Iterator<Item = Result<Pinyin, char>> + 'a {
+    s.chars().map(|c| p.char_reading(c).ok_or(c))
+}
+
+#[cfg(test)]
+#[cfg(feature = "embedded-data")]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("张三丰", ("张", "三丰"))]
+    #[case("欧阳修", ("欧阳", "修"))]
+    #[case("司马相如", ("司马", "相如"))]
+    #[case("欧阳", ("欧阳", ""))]
+    #[case("张", ("张", ""))]
+    #[case("", ("", ""))]
+    fn test_split_surname(#[case] name: &str, #[case] exp: (&str, &str)) {
+        assert_eq!(split_surname(name), exp);
+    }
+
+    #[rstest]
+    #[case(NameOrder::SurnameFirst, NameCase::Capitalized, "Zhang Sanfeng")]
+    #[case(NameOrder::SurnameFirst, NameCase::UpperSurname, "ZHANG Sanfeng")]
+    #[case(NameOrder::GivenNameFirst, NameCase::Capitalized, "Sanfeng Zhang")]
+    #[case(NameOrder::GivenNameFirst, NameCase::Upper, "SANFENG ZHANG")]
+    fn name_format(#[case] order: NameOrder, #[case] case: NameCase, #[case] exp: &str) {
+        let format = NameFormat {
+            order,
+            case,
+            ..Default::default()
+        };
+        assert_eq!(crate::romanize_name("张三丰", &format), exp);
+    }
+
+    #[rstest]
+    #[case("单雄信", "Shan Xiongxin")]
+    #[case("曾国藩", "Zeng Guofan")]
+    #[case("欧阳修", "Ouyang Xiu")]
+    #[case("司马迁", "Sima Qian")]
+    #[case("王小二", "Wang Xiao'er")]
+    #[case("张", "Zhang")]
+    fn test_romanize_name(#[case] name: &str, #[case] exp: &str) {
+        assert_eq!(crate::romanize_name(name, &NameFormat::default()), exp);
+    }
+
+    #[test]
+    fn romanize_name_with_tones() {
+        let format = NameFormat {
+            tone_repr: ToneRepresentation::Unicode,
+            ..Default::default()
+        };
+        assert_eq!(crate::romanize_name("解小东", &format), "Xiè Xiǎodōng");
+        assert_eq!(crate::romanize_name("仇英", &format), "Qiú Yīng");
+    }
+}
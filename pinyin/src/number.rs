@@ -0,0 +1,235 @@
+//! Spell Arabic numerals as Chinese number words, so they can be converted to pinyin.
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const DIGITS: [char; 10] = ['零', '一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+/// Digits of phone numbers, `1` is read as `幺` to avoid confusion with `七`.
+const PHONE_DIGITS: [char; 10] = ['零', '幺', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+const GROUP_UNITS: [&str; 4] = ["", "万", "亿", "万亿"];
+
+/// Max number of digits that read as a cardinal number, longer numbers are read digit by digit.
+const MAX_CARDINAL_DIGITS: usize = 16;
+
+/// Replace Arabic numerals with their Mandarin reading in Chinese characters.
+///
+/// - Years (4 digits, or 2 digits with a leading zero, followed by `年`) are read digit by
+///   digit: `2023年` -> `二零二三年`, while `10年` -> `十年`
+/// - Decimals: `3.14` -> `三点一四`
+/// - Percentages: `50%` -> `百分之五十`
+/// - Phone numbers (11 digits starting with `1`, digits starting with `0`, or digit groups
+///   joined by `-` such as `010-12345678` and `400-820-8820`) are read digit by digit, with
+///   `1` read as `幺`
+/// - Other numbers are read as cardinal numbers: `105` -> `一百零五`
+///
+/// Other characters are kept as is.
+pub fn read_numbers(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while !rest.is_empty() {
+        let digits_at = rest
+            .find(|c: char| c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        result.push_str(&rest[..digits_at]);
+        rest = &rest[digits_at..];
+        if !rest.is_empty() {
+            rest = read_number(rest, &mut result);
+        }
+    }
+    result
+}
+
+/// Read the number at the start of `s`, return the remaining string.
+fn read_number<'a>(s: &'a str, out: &mut String) -> &'a str {
+    let integer = take_digits(s);
+    let rest = &s[integer.len()..];
+
+    let year = integer.len() == 4 || (integer.len() == 2 && integer.starts_with('0'));
+    if rest.starts_with('年') && year {
+        write_digits(integer, out);
+        return rest;
+    }
+
+    if let Some(rest) = phone_number(s) {
+        for c in s[..s.len() - rest.len()].chars() {
+            match c.to_digit(10) {
+                Some(d) => out.push(PHONE_DIGITS[d as usize]),
+                None => out.push(c),
+            }
+        }
+        return rest;
+    }
+
+    let (fraction, rest) = match rest.strip_prefix('.') {
+        Some(r) if r.starts_with(|c: char| c.is_ascii_digit()) => {
+            let fraction = take_digits(r);
+            (Some(fraction), &r[fraction.len()..])
+        }
+        _ => (None, rest),
+    };
+    let (percent, rest) = match rest.strip_prefix(['%', '％']) {
+        Some(r) => (true, r),
+        None => (false, rest),
+    };
+
+    if percent {
+        out.push_str("百分之");
+    }
+    write_cardinal(integer, out);
+    if let Some(fraction) = fraction {
+        out.push('点');
+        write_digits(fraction, out);
+    }
+    rest
+}
+
+fn take_digits(s: &str) -> &str {
+    let end = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    &s[..end]
+}
+
+/// If `s` starts with a phone number, return the string after it.
+fn phone_number(s: &str) -> Option<&str> {
+    let first = take_digits(s);
+    let rest = &s[first.len()..];
+    let mut grouped_rest = rest;
+    let (mut groups, mut total, mut shortest) = (1, first.len(), first.len());
+    while let Some(r) = grouped_rest.strip_prefix('-') {
+        let group = take_digits(r);
+        if group.is_empty() {
+            break;
+        }
+        groups += 1;
+        total += group.len();
+        shortest = shortest.min(group.len());
+        grouped_rest = &r[group.len()..];
+    }
+
+    // such as 010-12345678, 138-0013-8000 and 400-820-8820, but not dates or ranges
+    let is_grouped =
+        groups > 1 && (3..=4).contains(&first.len()) && shortest >= 3 && (10..=12).contains(&total);
+    let is_mobile = first.len() == 11 && first.starts_with('1');
+    let leading_zero = first.len() > 1 && first.starts_with('0') && !rest.starts_with('.');
+    if is_grouped {
+        Some(grouped_rest)
+    } else if is_mobile || leading_zero {
+        Some(rest)
+    } else {
+        None
+    }
+}
+
+fn write_digits(digits: &str, out: &mut String) {
+    out.extend(digits.bytes().map(|b| DIGITS[(b - b'0') as usize]));
+}
+
+/// Write cardinal reading of a digit string, such as `一万零五`.
+fn write_cardinal(digits: &str, out: &mut String) {
+    let digits = digits.trim_start_matches('0');
+    if digits.is_empty() {
+        out.push(DIGITS[0]);
+        return;
+    }
+    if digits.len() > MAX_CARDINAL_DIGITS {
+        write_digits(digits, out);
+        return;
+    }
+
+    let mut n: u64 = digits.parse().unwrap();
+    let mut groups = Vec::with_capacity(4);
+    while n > 0 {
+        groups.push((n % 10000) as u16);
+        n /= 10000;
+    }
+
+    let mut started = false;
+    let mut pending_zero = false;
+    for (i, &group) in groups.iter().enumerate().rev() {
+        if group == 0 {
+            pending_zero |= started;
+            continue;
+        }
+        if started && (pending_zero || group < 1000) {
+            out.push(DIGITS[0]);
+        }
+        write_group(group, !started, out);
+        out.push_str(GROUP_UNITS[i]);
+        started = true;
+        pending_zero = false;
+    }
+}
+
+/// Write a group of 4 digits, `leading` is true if it is the first group of the number,
+/// which reads `10` as `十` instead of `一十`.
+fn write_group(group: u16, leading: bool, out: &mut String) {
+    const UNITS: [&str; 4] = ["千", "百", "十", ""];
+    let digits = [group / 1000, group / 100 % 10, group / 10 % 10, group % 10];
+    let mut started = false;
+    let mut pending_zero = false;
+    for (i, &d) in digits.iter().enumerate() {
+        if d == 0 {
+            pending_zero |= started;
+            continue;
+        }
+        if pending_zero {
+            out.push(DIGITS[0]);
+            pending_zero = false;
+        }
+        if !(d == 1 && i == 2 && leading && !started) {
+            out.push(DIGITS[d as usize]);
+        }
+        out.push_str(UNITS[i]);
+        started = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("0", "零")]
+    #[case("7", "七")]
+    #[case("10", "十")]
+    #[case("15", "十五")]
+    #[case("20", "二十")]
+    #[case("105", "一百零五")]
+    #[case("1010", "一千零一十")]
+    #[case("10001", "一万零一")]
+    #[case("100000", "十万")]
+    #[case("20230", "二万零二百三十")]
+    #[case("100010000", "一亿零一万")]
+    #[case("12345678901234567", "一二三四五六七八九零一二三四五六七")]
+    fn cardinal(#[case] digits: &str, #[case] exp: &str) {
+        let mut s = String::new();
+        write_cardinal(digits, &mut s);
+        assert_eq!(s, exp);
+    }
+
+    #[rstest]
+    #[case("2023年3月5日", "二零二三年三月五日")]
+    #[case("3.14", "三点一四")]
+    #[case("0.05", "零点零五")]
+    #[case("涨了50%", "涨了百分之五十")]
+    #[case("12.5％", "百分之十二点五")]
+    #[case("13800138000", "幺三八零零幺三八零零零")]
+    #[case("010-12345678", "零幺零-幺二三四五六七八")]
+    #[case("138-0013-8000", "幺三八-零零幺三-八零零零")]
+    #[case("400-820-8820", "四零零-八二零-八八二零")]
+    #[case("2023-10-18", "二千零二十三-十-十八")]
+    #[case("1-2", "一-二")]
+    #[case("1000-2000", "一千-二千")]
+    #[case("10年", "十年")]
+    #[case("30年", "三十年")]
+    #[case("08年", "零八年")]
+    #[case("01年", "零一年")]
+    #[case("100年", "一百年")]
+    #[case("共105人", "共一百零五人")]
+    #[case("v1.", "v一.")]
+    #[case("你好", "你好")]
+    fn test_read_numbers(#[case] s: &str, #[case] exp: &str) {
+        assert_eq!(read_numbers(s), exp);
+    }
+}
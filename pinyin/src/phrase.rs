@@ -0,0 +1,127 @@
+//! Readings of phrases, used by both the pinyin database and the user dictionary.
+use crate::Pinyin;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+/// Error of adding a phrase whose number of readings differs from its number of chars.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadingCountMismatch {
+    pub phrase: String,
+    pub readings: usize,
+}
+
+impl Display for ReadingCountMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "phrase '{}' has {} chars but {} readings",
+            self.phrase,
+            self.phrase.chars().count(),
+            self.readings
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadingCountMismatch {}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Phrases {
+    map: BTreeMap<String, Vec<Pinyin>>,
+    /// Number of chars of the longest phrase.
+    max_len: usize,
+}
+
+// some methods are only used by the user dictionary
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+impl Phrases {
+    pub(crate) const fn new() -> Self {
+        Self {
+            map: BTreeMap::new(),
+            max_len: 0,
+        }
+    }
+
+    /// Set readings of a phrase, one reading for each char. Return previous readings if exist.
+    pub(crate) fn insert(
+        &mut self,
+        phrase: &str,
+        readings: Vec<Pinyin>,
+    ) -> Result<Option<Vec<Pinyin>>, ReadingCountMismatch> {
+        let len = phrase.chars().count();
+        if len != readings.len() {
+            return Err(ReadingCountMismatch {
+                phrase: phrase.to_string(),
+                readings: readings.len(),
+            });
+        }
+        self.max_len = self.max_len.max(len);
+        Ok(self.map.insert(phrase.to_string(), readings))
+    }
+
+    pub(crate) fn remove(&mut self, phrase: &str) -> Option<Vec<Pinyin>> {
+        let r = self.map.remove(phrase);
+        self.max_len = self
+            .map
+            .keys()
+            .map(|p| p.chars().count())
+            .max()
+            .unwrap_or(0);
+        r
+    }
+
+    pub(crate) fn get(&self, phrase: &str) -> Option<&[Pinyin]> {
+        self.map.get(phrase).map(Vec::as_slice)
+    }
+
+    /// Iterate over phrases in the order of their text.
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&str, &[Pinyin])> {
+        self.map.iter().map(|(p, r)| (p.as_str(), r.as_slice()))
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.map.clear();
+        self.max_len = 0;
+    }
+
+    /// Lowest first char of all phrases.
+    pub(crate) fn first_char(&self) -> Option<char> {
+        self.map.keys().next().and_then(|p| p.chars().next())
+    }
+
+    /// Number of chars of the longest phrase.
+    pub(crate) fn max_len(&self) -> usize {
+        self.max_len
+    }
+
+    /// Find the longest phrase at the start of `s`, return the phrase length in bytes and
+    /// its readings.
+    pub(crate) fn match_prefix(&self, s: &str) -> Option<(usize, &[Pinyin])> {
+        longest_prefix(s, self.max_len, |p| self.get(p))
+    }
+}
+
+/// Find the longest prefix of `s` of at least 2 and at most `max_len` chars that `get`
+/// returns a value, return the prefix length in bytes and the value.
+pub(crate) fn longest_prefix<'a, T>(
+    s: &'a str,
+    max_len: usize,
+    get: impl Fn(&'a str) -> Option<T>,
+) -> Option<(usize, T)> {
+    if max_len < 2 {
+        return None;
+    }
+    let end = s.char_indices().nth(max_len).map_or(s.len(), |(i, _)| i);
+    let second = s.char_indices().nth(1)?.0;
+    // ends of prefixes from the longest down to 2 chars, walked without collecting
+    core::iter::once(end)
+        .chain(s[..end].char_indices().rev().map(|(i, _)| i))
+        .take_while(|&i| i > second)
+        .find_map(|i| get(&s[..i]).map(|r| (i, r)))
+}
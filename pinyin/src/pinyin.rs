@@ -1,7 +1,9 @@
+use crate::syllable::{self, is_pinyin_char};
+use crate::ToneRepresentation;
+use alloc::string::{String, ToString};
+use core::fmt::{Debug, Display, Formatter};
+use core::str::FromStr;
 use modular_bitfield::prelude::{bitfield, BitfieldSpecifier};
-use std::error::Error;
-use std::fmt::{Debug, Display, Formatter};
-use std::str::FromStr;
 
 #[derive(
     Debug,
@@ -13,6 +15,7 @@ use std::str::FromStr;
     strum_macros::Display,
     strum_macros::AsRefStr,
     strum_macros::EnumIter,
+    strum_macros::IntoStaticStr,
 )]
 #[bits = 5]
 #[strum(serialize_all = "snake_case")]
@@ -100,6 +103,15 @@ pub enum Finals {
     VE,
     #[strum(serialize = "üan")]
     Van,
+    /// Syllabic m, as in `ḿ` and `hm`.
+    M,
+    /// Syllabic n, as in `ń`.
+    N,
+    /// Syllabic ng, as in `ńg` and `hng`.
+    NG,
+    /// ê, as in `ê̄`.
+    #[strum(serialize = "ê")]
+    EH,
 }
 
 #[derive(
@@ -111,6 +123,7 @@ pub enum Finals {
     BitfieldSpecifier,
     strum_macros::Display,
     strum_macros::EnumIter,
+    strum_macros::IntoStaticStr,
 )]
 #[bits = 3]
 pub enum Tones {
@@ -134,6 +147,21 @@ pub struct Pinyin {
     finals: Finals,
 }
 
+impl Pinyin {
+    /// The same syllable without tone.
+    pub(crate) fn toneless(self) -> Pinyin {
+        self.with_tones(Tones::None)
+    }
+
+    /// The syllable as typed on a keyboard, without tone and `ü` as `v`. `None` if it can
+    /// not be typed by ASCII letters, such as `ê`.
+    #[cfg(feature = "std")]
+    pub(crate) fn typed(self) -> Option<String> {
+        let s = PinyinDisplay::NoTones(self).to_string().replace('ü', "v");
+        s.bytes().all(|b| b.is_ascii_lowercase()).then_some(s)
+    }
+}
+
 impl From<Pinyin> for u16 {
     fn from(p: Pinyin) -> Self {
         let bytes = p.into_bytes();
@@ -148,7 +176,15 @@ impl From<u16> for Pinyin {
     }
 }
 
+/// Whether `value` is the `u16` representation of a valid `Pinyin`.
+#[cfg(feature = "std")]
+pub(crate) fn is_valid_pinyin(value: u16) -> bool {
+    let p = Pinyin::from(value);
+    p.tones_or_err().is_ok() && p.initials_or_err().is_ok() && p.finals_or_err().is_ok()
+}
+
 /// create a new pinyin syllable
+#[cfg(test)]
 pub fn py(initials: Initials, finals: Finals, tones: Tones) -> Pinyin {
     let mut r = Pinyin::new();
     r.set_initials(initials);
@@ -158,7 +194,7 @@ pub fn py(initials: Initials, finals: Finals, tones: Tones) -> Pinyin {
 }
 
 impl Display for Pinyin {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
         PinyinDisplay::UnicodeTone(*self).fmt(f)
     }
 }
@@ -170,221 +206,121 @@ pub enum PinyinDisplay {
     FirstLetter(Pinyin),
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
-pub(crate) struct FinalWithTones(pub(crate) Finals, pub(crate) Tones);
+impl PinyinDisplay {
+    pub fn new(pinyin: Pinyin, tone_repr: ToneRepresentation) -> Self {
+        match tone_repr {
+            ToneRepresentation::None => PinyinDisplay::NoTones(pinyin),
+            ToneRepresentation::Numbered => PinyinDisplay::NumberedTone(pinyin),
+            ToneRepresentation::Unicode => PinyinDisplay::UnicodeTone(pinyin),
+        }
+    }
 
-#[derive(Debug)]
-pub(crate) struct FinalWithTonesFromStrError(String);
+    /// Static strings that make up the display of the syllable, such as `zh` and `ōng` of
+    /// `zhōng`, or `zh`, `ong` and `1` of `zhong1`. Empty strings are skipped.
+    pub fn pieces(&self) -> impl Iterator<Item = &'static str> {
+        let pieces: [&'static str; 3] = match *self {
+            PinyinDisplay::UnicodeTone(p) => [
+                p.initials().into(),
+                FinalWithTones(p.finals(), p.tones()).into(),
+                "",
+            ],
+            PinyinDisplay::NumberedTone(p) => {
+                [p.initials().into(), p.finals().into(), p.tones().into()]
+            }
+            PinyinDisplay::NoTones(p) => [p.initials().into(), p.finals().into(), ""],
+            PinyinDisplay::FirstLetter(p) => {
+                let letter = match (p.initials(), p.finals()) {
+                    (Initials::None, Finals::EH) => "e",
+                    (Initials::None, finals) => &<&'static str>::from(finals)[0..1],
+                    (initials, _) => &<&'static str>::from(initials)[0..1],
+                };
+                [letter, "", ""]
+            }
+        };
+        pieces.into_iter().filter(|s| !s.is_empty())
+    }
+}
 
-impl Display for FinalWithTonesFromStrError {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "invalid final with tones: '{}'", self.0)
+/// Write syllables of a word without spaces, such as `Xī'ān`. A syllable starts with
+/// a, o or e is separated from the previous syllable by an apostrophe.
+#[cfg(feature = "std")]
+pub(crate) fn write_word(
+    w: &mut impl core::fmt::Write,
+    syllables: &[Pinyin],
+    tone_repr: ToneRepresentation,
+) -> core::fmt::Result {
+    for (i, &p) in syllables.iter().enumerate() {
+        if i > 0
+            && p.initials() == Initials::None
+            && p.finals().as_ref().starts_with(['a', 'o', 'e', 'ê'])
+        {
+            w.write_char('\'')?;
+        }
+        write!(w, "{}", PinyinDisplay::new(p, tone_repr))?;
     }
+    Ok(())
 }
 
-impl Error for FinalWithTonesFromStrError {}
+/// Join syllables as one word, chars without reading are kept as is.
+#[cfg(feature = "std")]
+pub(crate) fn word(
+    chars: impl Iterator<Item = Result<Pinyin, char>>,
+    tone_repr: ToneRepresentation,
+) -> String {
+    let mut syllables = vec![];
+    let mut result = String::new();
+    for c in chars {
+        match c {
+            Ok(p) => syllables.push(p),
+            Err(c) => {
+                write_word(&mut result, &syllables, tone_repr).unwrap();
+                syllables.clear();
+                result.push(c);
+            }
+        }
+    }
+    write_word(&mut result, &syllables, tone_repr).unwrap();
+    result
+}
 
-impl FromStr for FinalWithTones {
-    type Err = FinalWithTonesFromStrError;
+/// Upper case the first letter of `s`.
+#[cfg(feature = "std")]
+pub(crate) fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinyinFromStrError(pub(crate) String);
+
+impl Display for PinyinFromStrError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        write!(f, "invalid pinyin: '{}'", self.0)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for PinyinFromStrError {}
+
+impl FromStr for Pinyin {
+    type Err = PinyinFromStrError;
 
+    /// Parse a pinyin syllable with unicode tone mark, such as `zhōng`.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let r = match s {
-            "a" => FinalWithTones(Finals::A, Tones::None),
-            "o" => FinalWithTones(Finals::O, Tones::None),
-            "e" => FinalWithTones(Finals::E, Tones::None),
-            "u" => FinalWithTones(Finals::U, Tones::None),
-            "i" => FinalWithTones(Finals::I, Tones::None),
-            "ai" => FinalWithTones(Finals::AI, Tones::None),
-            "ei" => FinalWithTones(Finals::EI, Tones::None),
-            "ou" => FinalWithTones(Finals::OU, Tones::None),
-            "an" => FinalWithTones(Finals::AN, Tones::None),
-            "en" => FinalWithTones(Finals::EN, Tones::None),
-            "ang" => FinalWithTones(Finals::Ang, Tones::None),
-            "eng" => FinalWithTones(Finals::Eng, Tones::None),
-            "er" => FinalWithTones(Finals::ER, Tones::None),
-            "ia" => FinalWithTones(Finals::IA, Tones::None),
-            "iā" => FinalWithTones(Finals::IA, Tones::One),
-            "iá" => FinalWithTones(Finals::IA, Tones::Two),
-            "iǎ" => FinalWithTones(Finals::IA, Tones::Three),
-            "ià" => FinalWithTones(Finals::IA, Tones::Four),
-            "ie" => FinalWithTones(Finals::IE, Tones::None),
-            "iē" => FinalWithTones(Finals::IE, Tones::One),
-            "ié" => FinalWithTones(Finals::IE, Tones::Two),
-            "iě" => FinalWithTones(Finals::IE, Tones::Three),
-            "iè" => FinalWithTones(Finals::IE, Tones::Four),
-            "iao" => FinalWithTones(Finals::Iao, Tones::None),
-            "iāo" => FinalWithTones(Finals::Iao, Tones::One),
-            "iáo" => FinalWithTones(Finals::Iao, Tones::Two),
-            "iǎo" => FinalWithTones(Finals::Iao, Tones::Three),
-            "iào" => FinalWithTones(Finals::Iao, Tones::Four),
-            "ao" => FinalWithTones(Finals::AO, Tones::None),
-            "āo" => FinalWithTones(Finals::AO, Tones::One),
-            "áo" => FinalWithTones(Finals::AO, Tones::Two),
-            "ǎo" => FinalWithTones(Finals::AO, Tones::Three),
-            "ào" => FinalWithTones(Finals::AO, Tones::Four),
-            "ian" => FinalWithTones(Finals::Ian, Tones::None),
-            "iān" => FinalWithTones(Finals::Ian, Tones::One),
-            "ián" => FinalWithTones(Finals::Ian, Tones::Two),
-            "iǎn" => FinalWithTones(Finals::Ian, Tones::Three),
-            "iàn" => FinalWithTones(Finals::Ian, Tones::Four),
-            "ing" => FinalWithTones(Finals::Ing, Tones::None),
-            "īng" => FinalWithTones(Finals::Ing, Tones::One),
-            "íng" => FinalWithTones(Finals::Ing, Tones::Two),
-            "ǐng" => FinalWithTones(Finals::Ing, Tones::Three),
-            "ìng" => FinalWithTones(Finals::Ing, Tones::Four),
-            "iang" => FinalWithTones(Finals::Iang, Tones::None),
-            "iāng" => FinalWithTones(Finals::Iang, Tones::One),
-            "iáng" => FinalWithTones(Finals::Iang, Tones::Two),
-            "iǎng" => FinalWithTones(Finals::Iang, Tones::Three),
-            "iàng" => FinalWithTones(Finals::Iang, Tones::Four),
-            "in" => FinalWithTones(Finals::IN, Tones::None),
-            "īn" => FinalWithTones(Finals::IN, Tones::One),
-            "ín" => FinalWithTones(Finals::IN, Tones::Two),
-            "ǐn" => FinalWithTones(Finals::IN, Tones::Three),
-            "ìn" => FinalWithTones(Finals::IN, Tones::Four),
-            "ui" => FinalWithTones(Finals::UI, Tones::None),
-            "uī" => FinalWithTones(Finals::UI, Tones::One),
-            "uí" => FinalWithTones(Finals::UI, Tones::Two),
-            "uǐ" => FinalWithTones(Finals::UI, Tones::Three),
-            "uì" => FinalWithTones(Finals::UI, Tones::Four),
-            "iou" => FinalWithTones(Finals::Iou, Tones::None),
-            "iōu" => FinalWithTones(Finals::Iou, Tones::One),
-            "ióu" => FinalWithTones(Finals::Iou, Tones::Two),
-            "iǒu" => FinalWithTones(Finals::Iou, Tones::Three),
-            "iòu" => FinalWithTones(Finals::Iou, Tones::Four),
-            "iong" => FinalWithTones(Finals::Iong, Tones::None),
-            "iōng" => FinalWithTones(Finals::Iong, Tones::One),
-            "ióng" => FinalWithTones(Finals::Iong, Tones::Two),
-            "iǒng" => FinalWithTones(Finals::Iong, Tones::Three),
-            "iòng" => FinalWithTones(Finals::Iong, Tones::Four),
-            "iu" => FinalWithTones(Finals::IU, Tones::None),
-            "iū" => FinalWithTones(Finals::IU, Tones::One),
-            "iú" => FinalWithTones(Finals::IU, Tones::Two),
-            "iǔ" => FinalWithTones(Finals::IU, Tones::Three),
-            "iù" => FinalWithTones(Finals::IU, Tones::Four),
-            "ua" => FinalWithTones(Finals::UA, Tones::None),
-            "uā" => FinalWithTones(Finals::UA, Tones::One),
-            "uá" => FinalWithTones(Finals::UA, Tones::Two),
-            "uǎ" => FinalWithTones(Finals::UA, Tones::Three),
-            "uà" => FinalWithTones(Finals::UA, Tones::Four),
-            "uai" => FinalWithTones(Finals::Uai, Tones::None),
-            "uāi" => FinalWithTones(Finals::Uai, Tones::One),
-            "uái" => FinalWithTones(Finals::Uai, Tones::Two),
-            "uǎi" => FinalWithTones(Finals::Uai, Tones::Three),
-            "uài" => FinalWithTones(Finals::Uai, Tones::Four),
-            "uan" => FinalWithTones(Finals::Uan, Tones::None),
-            "uān" => FinalWithTones(Finals::Uan, Tones::One),
-            "uán" => FinalWithTones(Finals::Uan, Tones::Two),
-            "uǎn" => FinalWithTones(Finals::Uan, Tones::Three),
-            "uàn" => FinalWithTones(Finals::Uan, Tones::Four),
-            "un" => FinalWithTones(Finals::UN, Tones::None),
-            "ūn" => FinalWithTones(Finals::UN, Tones::One),
-            "ún" => FinalWithTones(Finals::UN, Tones::Two),
-            "ǔn" => FinalWithTones(Finals::UN, Tones::Three),
-            "ùn" => FinalWithTones(Finals::UN, Tones::Four),
-            "uang" => FinalWithTones(Finals::Uang, Tones::None),
-            "uāng" => FinalWithTones(Finals::Uang, Tones::One),
-            "uáng" => FinalWithTones(Finals::Uang, Tones::Two),
-            "uǎng" => FinalWithTones(Finals::Uang, Tones::Three),
-            "uàng" => FinalWithTones(Finals::Uang, Tones::Four),
-            "uo" => FinalWithTones(Finals::UO, Tones::None),
-            "uō" => FinalWithTones(Finals::UO, Tones::One),
-            "uó" => FinalWithTones(Finals::UO, Tones::Two),
-            "uǒ" => FinalWithTones(Finals::UO, Tones::Three),
-            "uò" => FinalWithTones(Finals::UO, Tones::Four),
-            "ü" => FinalWithTones(Finals::V, Tones::None),
-            "ǖ" => FinalWithTones(Finals::V, Tones::One),
-            "ǘ" => FinalWithTones(Finals::V, Tones::Two),
-            "ǚ" => FinalWithTones(Finals::V, Tones::Three),
-            "ǜ" => FinalWithTones(Finals::V, Tones::Four),
-            "üe" => FinalWithTones(Finals::VE, Tones::None),
-            "üē" => FinalWithTones(Finals::VE, Tones::One),
-            "üé" => FinalWithTones(Finals::VE, Tones::Two),
-            "üě" => FinalWithTones(Finals::VE, Tones::Three),
-            "üè" => FinalWithTones(Finals::VE, Tones::Four),
-            "ue" => FinalWithTones(Finals::UE, Tones::None),
-            "uē" => FinalWithTones(Finals::UE, Tones::One),
-            "ué" => FinalWithTones(Finals::UE, Tones::Two),
-            "uě" => FinalWithTones(Finals::UE, Tones::Three),
-            "uè" => FinalWithTones(Finals::UE, Tones::Four),
-            "üan" => FinalWithTones(Finals::Van, Tones::None),
-            "ǖan" => FinalWithTones(Finals::Van, Tones::One),
-            "ǘan" => FinalWithTones(Finals::Van, Tones::Two),
-            "ǚan" => FinalWithTones(Finals::Van, Tones::Three),
-            "ǜan" => FinalWithTones(Finals::Van, Tones::Four),
-            "ā" => FinalWithTones(Finals::A, Tones::One),
-            "á" => FinalWithTones(Finals::A, Tones::Two),
-            "ǎ" => FinalWithTones(Finals::A, Tones::Three),
-            "à" => FinalWithTones(Finals::A, Tones::Four),
-            "ō" => FinalWithTones(Finals::O, Tones::One),
-            "ó" => FinalWithTones(Finals::O, Tones::Two),
-            "ǒ" => FinalWithTones(Finals::O, Tones::Three),
-            "ò" => FinalWithTones(Finals::O, Tones::Four),
-            "ē" => FinalWithTones(Finals::E, Tones::One),
-            "é" => FinalWithTones(Finals::E, Tones::Two),
-            "ě" => FinalWithTones(Finals::E, Tones::Three),
-            "è" => FinalWithTones(Finals::E, Tones::Four),
-            "ū" => FinalWithTones(Finals::U, Tones::One),
-            "ú" => FinalWithTones(Finals::U, Tones::Two),
-            "ǔ" => FinalWithTones(Finals::U, Tones::Three),
-            "ù" => FinalWithTones(Finals::U, Tones::Four),
-            "ī" => FinalWithTones(Finals::I, Tones::One),
-            "í" => FinalWithTones(Finals::I, Tones::Two),
-            "ǐ" => FinalWithTones(Finals::I, Tones::Three),
-            "ì" => FinalWithTones(Finals::I, Tones::Four),
-            "āi" => FinalWithTones(Finals::AI, Tones::One),
-            "ái" => FinalWithTones(Finals::AI, Tones::Two),
-            "ǎi" => FinalWithTones(Finals::AI, Tones::Three),
-            "ài" => FinalWithTones(Finals::AI, Tones::Four),
-            "ēi" => FinalWithTones(Finals::EI, Tones::One),
-            "éi" => FinalWithTones(Finals::EI, Tones::Two),
-            "ěi" => FinalWithTones(Finals::EI, Tones::Three),
-            "èi" => FinalWithTones(Finals::EI, Tones::Four),
-            "ōu" => FinalWithTones(Finals::OU, Tones::One),
-            "óu" => FinalWithTones(Finals::OU, Tones::Two),
-            "ǒu" => FinalWithTones(Finals::OU, Tones::Three),
-            "òu" => FinalWithTones(Finals::OU, Tones::Four),
-            "ong" => FinalWithTones(Finals::Ong, Tones::None),
-            "ōng" => FinalWithTones(Finals::Ong, Tones::One),
-            "óng" => FinalWithTones(Finals::Ong, Tones::Two),
-            "ǒng" => FinalWithTones(Finals::Ong, Tones::Three),
-            "òng" => FinalWithTones(Finals::Ong, Tones::Four),
-            "ān" => FinalWithTones(Finals::AN, Tones::One),
-            "án" => FinalWithTones(Finals::AN, Tones::Two),
-            "ǎn" => FinalWithTones(Finals::AN, Tones::Three),
-            "àn" => FinalWithTones(Finals::AN, Tones::Four),
-            "ēn" => FinalWithTones(Finals::EN, Tones::One),
-            "én" => FinalWithTones(Finals::EN, Tones::Two),
-            "ěn" => FinalWithTones(Finals::EN, Tones::Three),
-            "èn" => FinalWithTones(Finals::EN, Tones::Four),
-            "āng" => FinalWithTones(Finals::Ang, Tones::One),
-            "áng" => FinalWithTones(Finals::Ang, Tones::Two),
-            "ǎng" => FinalWithTones(Finals::Ang, Tones::Three),
-            "àng" => FinalWithTones(Finals::Ang, Tones::Four),
-            "ēng" => FinalWithTones(Finals::Eng, Tones::One),
-            "éng" => FinalWithTones(Finals::Eng, Tones::Two),
-            "ěng" => FinalWithTones(Finals::Eng, Tones::Three),
-            "èng" => FinalWithTones(Finals::Eng, Tones::Four),
-            "ēr" => FinalWithTones(Finals::ER, Tones::One),
-            "ér" => FinalWithTones(Finals::ER, Tones::Two),
-            "ěr" => FinalWithTones(Finals::ER, Tones::Three),
-            "èr" => FinalWithTones(Finals::ER, Tones::Four),
-            "ń" => FinalWithTones(Finals::EN, Tones::Two),
-            "ň" => FinalWithTones(Finals::EN, Tones::Three),
-            "ǹ" => FinalWithTones(Finals::EN, Tones::Four),
-            "ńg" => FinalWithTones(Finals::Eng, Tones::Two),
-            "ňg" => FinalWithTones(Finals::Eng, Tones::Three),
-            "ǹg" => FinalWithTones(Finals::Eng, Tones::Four),
-            _ => FinalWithTones(Finals::None, Tones::None),
-        };
-        if let FinalWithTones(Finals::None, Tones::None) = r {
-            Err(FinalWithTonesFromStrError(s.to_string()))
-        } else {
-            Ok(r)
-        }
+        Some(s)
+            .filter(|s| s.chars().all(is_pinyin_char))
+            .and_then(syllable::parse)
+            .map(Pinyin::from)
+            .ok_or_else(|| PinyinFromStrError(s.to_string()))
     }
 }
 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct FinalWithTones(pub(crate) Finals, pub(crate) Tones);
+
 impl From<FinalWithTones> for &'static str {
     fn from(value: FinalWithTones) -> Self {
         match (value.0, value.1) {
@@ -533,6 +469,22 @@ impl From<FinalWithTones> for &'static str {
             (Finals::Van, Tones::Two) => "ǘan",
             (Finals::Van, Tones::Three) => "ǚan",
             (Finals::Van, Tones::Four) => "ǜan",
+            (Finals::M, Tones::One) => "m\u{304}",
+            (Finals::M, Tones::Two) => "ḿ",
+            (Finals::M, Tones::Three) => "m\u{30c}",
+            (Finals::M, Tones::Four) => "m\u{300}",
+            (Finals::N, Tones::One) => "n\u{304}",
+            (Finals::N, Tones::Two) => "ń",
+            (Finals::N, Tones::Three) => "ň",
+            (Finals::N, Tones::Four) => "ǹ",
+            (Finals::NG, Tones::One) => "n\u{304}g",
+            (Finals::NG, Tones::Two) => "ńg",
+            (Finals::NG, Tones::Three) => "ňg",
+            (Finals::NG, Tones::Four) => "ǹg",
+            (Finals::EH, Tones::One) => "ê\u{304}",
+            (Finals::EH, Tones::Two) => "ế",
+            (Finals::EH, Tones::Three) => "ê\u{30c}",
+            (Finals::EH, Tones::Four) => "ề",
             (Finals::None, Tones::One) => unreachable!(),
             (Finals::None, Tones::Two) => unreachable!(),
             (Finals::None, Tones::Three) => unreachable!(),
@@ -542,30 +494,8 @@ impl From<FinalWithTones> for &'static str {
 }
 
 impl Display for PinyinDisplay {
-    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        match self {
-            PinyinDisplay::UnicodeTone(p) => {
-                f.write_fmt(format_args!("{}", p.initials()))?;
-                if p.tones() == Tones::None {
-                    f.write_fmt(format_args!("{}", p.finals()))
-                } else {
-                    f.write_str(FinalWithTones(p.finals(), p.tones()).into())
-                }
-            }
-            PinyinDisplay::NumberedTone(p) => {
-                f.write_fmt(format_args!("{}", p.initials()))?;
-                f.write_fmt(format_args!("{}", p.finals()))?;
-                f.write_fmt(format_args!("{}", p.tones()))
-            }
-            PinyinDisplay::NoTones(p) => {
-                f.write_fmt(format_args!("{}", p.initials()))?;
-                f.write_fmt(format_args!("{}", p.finals()))
-            }
-            PinyinDisplay::FirstLetter(p) => match p.initials() {
-                Initials::None => f.write_str(&p.finals().as_ref()[0..1]),
-                _ => f.write_str(&p.initials().as_ref()[0..1]),
-            },
-        }
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.pieces().try_for_each(|s| f.write_str(s))
     }
 }
 
@@ -597,6 +527,9 @@ mod tests {
     #[case("beng", py(Initials::B, Finals::Eng, Tones::None))]
     #[case("zhǖ", py(Initials::ZH, Finals::V, Tones::One))]
     #[case("ēr", py(Initials::None, Finals::ER, Tones::One))]
+    #[case("ńg", py(Initials::None, Finals::NG, Tones::Two))]
+    #[case("hm", py(Initials::H, Finals::M, Tones::None))]
+    #[case("ề", py(Initials::None, Finals::EH, Tones::Four))]
     fn pinyin_unicode_format(#[case] exp: &str, #[case] val: Pinyin) {
         assert_eq!(exp, val.to_string());
     }
@@ -629,17 +562,47 @@ mod tests {
     #[case("a", py(Initials::None, Finals::A, Tones::One))]
     #[case("e", py(Initials::None, Finals::ER, Tones::One))]
     #[case("s", py(Initials::SH, Finals::I, Tones::One))]
+    #[case("e", py(Initials::None, Finals::EH, Tones::Two))]
+    #[case("n", py(Initials::None, Finals::NG, Tones::Two))]
     fn first_letter_format(#[case] exp: &str, #[case] val: Pinyin) {
         assert_eq!(exp, PinyinDisplay::FirstLetter(val).to_string());
     }
 
+    #[rstest]
+    #[case(&["zh", "ōng"][..], PinyinDisplay::UnicodeTone(py(Initials::ZH, Finals::Ong, Tones::One)))]
+    #[case(&["zh", "ong", "1"][..], PinyinDisplay::NumberedTone(py(Initials::ZH, Finals::Ong, Tones::One)))]
+    #[case(&["ā"][..], PinyinDisplay::UnicodeTone(py(Initials::None, Finals::A, Tones::One)))]
+    #[case(&["a"][..], PinyinDisplay::NumberedTone(py(Initials::None, Finals::A, Tones::None)))]
+    #[case(&["b", "eng"][..], PinyinDisplay::NoTones(py(Initials::B, Finals::Eng, Tones::Two)))]
+    #[case(&["z"][..], PinyinDisplay::FirstLetter(py(Initials::ZH, Finals::Ong, Tones::One)))]
+    fn pieces(#[case] exp: &[&str], #[case] val: PinyinDisplay) {
+        assert_eq!(val.pieces().collect::<Vec<_>>(), exp);
+    }
+
+    #[rstest]
+    #[case("xī'ān", &["xī", "ān"])]
+    #[case("zhōngguó", &["zhōng", "guó"])]
+    #[case("ér", &["ér"])]
+    #[case("ń'ế", &["ń", "ế"])]
+    fn word_format(#[case] exp: &str, #[case] syllables: &[&str]) {
+        let syllables: Vec<Pinyin> = syllables.iter().map(|s| s.parse().unwrap()).collect();
+        let mut s = String::new();
+        write_word(&mut s, &syllables, ToneRepresentation::Unicode).unwrap();
+        assert_eq!(exp, s);
+    }
+
+    #[test]
+    fn test_capitalize() {
+        assert_eq!(capitalize("ōuyáng"), "Ōuyáng");
+        assert_eq!(capitalize(""), "");
+    }
+
     #[test]
     fn final_and_tones_to_from_str() {
         itertools::iproduct!(Finals::iter(), Tones::iter()).for_each(|(f, t)| {
             if f != Finals::None {
                 let s: &str = FinalWithTones(f, t).into();
-                let fwt = s.parse::<FinalWithTones>().unwrap();
-                assert_eq!(fwt, FinalWithTones(f, t));
+                assert_eq!(s.parse::<Pinyin>(), Ok(py(Initials::None, f, t)));
             }
         });
     }
@@ -0,0 +1,331 @@
+use crate::db::{LayeredDB, ParseErrors, DB};
+use crate::pinyin::PinyinDisplay;
+use crate::{
+    address, matcher, name, scan, FuzzyMatch, NameFormat, Pinyin, ToneRepresentation, UserDict,
+};
+use std::fmt;
+use std::sync::RwLock;
+
+/// Convert Chinese text to pinyin with its own pinyin database and user dictionary.
+///
+/// Use different instances to hold different datasets in one process, share an instance
+/// between threads by `Arc`.
+pub struct Pinyinizer {
+    db: LayeredDB,
+    user_dict: RwLock<UserDict>,
+}
+
+impl Pinyinizer {
+    pub fn new(db: DB) -> Self {
+        Self::with_layers(db.into())
+    }
+
+    /// Create from layered databases, such as a base dataset with overlays of a company and
+    /// a tenant.
+    pub fn with_layers(db: LayeredDB) -> Self {
+        Self {
+            db,
+            user_dict: RwLock::new(UserDict::new()),
+        }
+    }
+
+    /// Create from the pinyin data embedded in the crate, its tables are static and need no
+    /// parsing at runtime.
+    #[cfg(feature = "embedded-data")]
+    pub fn embedded() -> Self {
+        Self::new(crate::db::EMBEDDED.clone())
+    }
+
+    /// Create from pinyin data in the same syntax as the embedded `pinyin.txt`.
+    pub fn from_data(s: &str) -> Result<Self, ParseErrors> {
+        Ok(Self::new(DB::load(s)?))
+    }
+
+    pub fn db(&self) -> &LayeredDB {
+        &self.db
+    }
+
+    /// User dictionary to override readings of chars and phrases, it takes precedence over
+    /// the pinyin database in all conversions.
+    pub fn user_dict(&self) -> &RwLock<UserDict> {
+        &self.user_dict
+    }
+
+    /// Return pinyin of a Chinese characters separated by space.
+    pub fn pinyin(&self, s: &str, tone_repr: ToneRepresentation) -> String {
+        let mut result = String::new();
+        self.pinyin_into(s, tone_repr, &mut result).unwrap();
+        result
+    }
+
+    /// Write the result of [`Pinyinizer::pinyin`] to `w` without allocation.
+    pub fn pinyin_into(
+        &self,
+        s: &str,
+        tone_repr: ToneRepresentation,
+        w: &mut impl fmt::Write,
+    ) -> fmt::Result {
+        let mut result = Ok(());
+        self.for_each_reading(s, |r| {
+            if result.is_ok() {
+                result = match r {
+                    Piece::Reading(pinyin) => {
+                        write_pieces(w, PinyinDisplay::new(pinyin, tone_repr))
+                    }
+                    Piece::Char(c) => w.write_char(c),
+                    Piece::Run(run) => w.write_str(run),
+                }
+                .and_then(|_| w.write_char(' '));
+            }
+        });
+        result
+    }
+
+    /// Replace Chinese characters with their first letter. Ignore non-printable characters.
+    /// Non Chinese characters are kept as is.
+    pub fn first_letters(&self, s: &str) -> String {
+        let mut result = String::new();
+        self.first_letters_into(s, &mut result).unwrap();
+        result
+    }
+
+    /// Write the result of [`Pinyinizer::first_letters`] to `w` without allocation.
+    pub fn first_letters_into(&self, s: &str, w: &mut impl fmt::Write) -> fmt::Result {
+        let mut result = Ok(());
+        self.for_each_reading(s, |r| {
+            if result.is_ok() {
+                result = match r {
+                    Piece::Reading(pinyin) => write_pieces(w, PinyinDisplay::FirstLetter(pinyin)),
+                    Piece::Char(c) => w.write_char(c),
+                    Piece::Run(run) => w.write_str(run),
+                };
+            }
+        });
+        result
+    }
+
+    /// See [`crate::romanize_name`].
+    pub fn romanize_name(&self, name: &str, format: &NameFormat) -> String {
+        name::romanize_name(self, name, format)
+    }
+
+    /// See [`crate::romanize_place`].
+    pub fn romanize_place(&self, name: &str, tone_repr: ToneRepresentation) -> String {
+        address::romanize_place(self, name, tone_repr)
+    }
+
+    /// See [`crate::romanize_address`].
+    pub fn romanize_address(&self, address: &str, tone_repr: ToneRepresentation) -> String {
+        address::romanize_address(self, address, tone_repr)
+    }
+
+    /// Match `query` typed in a search box against `text` by pinyin, return `None` if it
+    /// does not match. See [`crate::fuzzy_match`].
+    pub fn fuzzy_match(&self, query: &str, text: &str) -> Option<FuzzyMatch> {
+        matcher::fuzzy_match(self, query, text)
+    }
+
+    /// All readings of a char, user dictionary takes precedence.
+    pub(crate) fn char_readings(&self, c: char) -> Vec<Pinyin> {
+        let user_dict = self.user_dict.read().unwrap();
+        match user_dict.get_char(c) {
+            Some(readings) => readings.to_vec(),
+            None => self.db.get(c).map_or(vec![], |f| f.value.iter().collect()),
+        }
+    }
+
+    /// Most common reading of a char, user dictionary takes precedence.
+    pub(crate) fn char_reading(&self, c: char) -> Option<Pinyin> {
+        let user_dict = self.user_dict.read().unwrap();
+        self.char_reading_with(&user_dict, c)
+    }
+
+    fn char_reading_with(&self, user_dict: &UserDict, c: char) -> Option<Pinyin> {
+        match user_dict.get_char(c).and_then(|r| r.first()) {
+            Some(p) => Some(*p),
+            None => self.db.get(c).map(|f| Pinyin::from(f.value)),
+        }
+    }
+
+    /// Call `f` with the reading of each char of `s`, or the char itself if it has no
+    /// reading. Phrases in user dictionary are matched first, then phrases of the database,
+    /// longest phrase wins.
+    ///
+    /// Runs of text before the lowest char of the user dictionary and the database, such as
+    /// ASCII, are found by vectorized scan and passed as a whole, without decoding and
+    /// looking up each char.
+    fn for_each_reading<'a>(&self, s: &'a str, mut f: impl FnMut(Piece<'a>)) {
+        let user_dict = self.user_dict.read().unwrap();
+        let threshold = scan::threshold(user_dict.lowest_char().min(self.db.lowest_char()));
+        let mut rest = s;
+        while let Some(c) = rest.chars().next() {
+            if rest.as_bytes()[0] < threshold {
+                let skipped = scan::skip_below(rest.as_bytes(), threshold);
+                f(Piece::Run(&rest[..skipped]));
+                rest = &rest[skipped..];
+                continue;
+            }
+            let phrase = user_dict.match_phrase(rest);
+            if let Some((len, readings)) = phrase.or_else(|| self.db.match_phrase(rest)) {
+                readings.iter().for_each(|p| f(Piece::Reading(*p)));
+                rest = &rest[len..];
+            } else {
+                f(self
+                    .char_reading_with(&user_dict, c)
+                    .map_or(Piece::Char(c), Piece::Reading));
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+}
+
+#[cfg(feature = "embedded-data")]
+impl Default for Pinyinizer {
+    fn default() -> Self {
+        Self::embedded()
+    }
+}
+
+/// Text of `Pinyinizer::for_each_reading`.
+enum Piece<'a> {
+    /// Reading of a char.
+    Reading(Pinyin),
+    /// A char without reading.
+    Char(char),
+    /// A run of text without readings, written as is.
+    Run(&'a str),
+}
+
+/// Write the syllable by its static pieces, it skips the formatting machinery of `write!`.
+fn write_pieces(w: &mut impl fmt::Write, p: PinyinDisplay) -> fmt::Result {
+    p.pieces().try_for_each(|s| w.write_str(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Layer;
+
+    #[test]
+    fn custom_data() {
+        let p = Pinyinizer::from_data("U+4F60: nì\nU+597D: hào\n").unwrap();
+        assert_eq!(
+            p.pinyin("你好吗", ToneRepresentation::Numbered),
+            "ni4 hao4 吗 "
+        );
+        assert_eq!(p.first_letters("你好吗"), "nh吗");
+    }
+
+    #[test]
+    fn db_phrases() {
+        let mut db = DB::load("U+4F60: nǐ\nU+597D: hǎo\n").unwrap();
+        db.load_phrases("好好: hǎo hāo\n").unwrap();
+        let p = Pinyinizer::new(db);
+        assert_eq!(
+            p.pinyin("你好好", ToneRepresentation::Unicode),
+            "nǐ hǎo hāo "
+        );
+        p.user_dict()
+            .write()
+            .unwrap()
+            .insert_phrase("你好", vec!["nī".parse().unwrap(), "hào".parse().unwrap()])
+            .unwrap();
+        assert_eq!(
+            p.pinyin("你好好", ToneRepresentation::Unicode),
+            "nī hào hǎo "
+        );
+    }
+
+    #[test]
+    fn layers() {
+        let mut db = LayeredDB::from(DB::load("U+4F60: nǐ\nU+597D: hǎo\n").unwrap());
+        let mut tenant = Layer::new("tenant");
+        tenant.set_char('你', &["nín".parse().unwrap()]);
+        tenant.delete_char('好');
+        db.push(tenant);
+        let p = Pinyinizer::with_layers(db);
+        assert_eq!(p.pinyin("你好", ToneRepresentation::Unicode), "nín 好 ");
+        assert_eq!(p.db().get('你').unwrap().layer, "tenant");
+    }
+
+    #[test]
+    fn skip_text_without_reading() {
+        let mut db = DB::load("U+4E2D: zhōng\n").unwrap();
+        let p = Pinyinizer::new(db.clone());
+        assert_eq!(p.first_letters("T-shirt 中 ok"), "T-shirt z ok");
+        assert_eq!(p.pinyin("a中", ToneRepresentation::Numbered), "a zhong1 ");
+        // runs without reading are written as a whole, followed by one space
+        assert_eq!(
+            p.pinyin("ok 中 ok", ToneRepresentation::Numbered),
+            "ok  zhong1  ok "
+        );
+
+        // ASCII with readings is not skipped
+        db.load_phrases("T恤: tī xù\n").unwrap();
+        let p = Pinyinizer::new(db);
+        assert_eq!(p.first_letters("大T恤"), "大tx");
+        p.user_dict()
+            .write()
+            .unwrap()
+            .insert_char('k', vec!["hā".parse().unwrap()]);
+        assert_eq!(p.first_letters("ok中"), "ohz");
+        p.user_dict()
+            .write()
+            .unwrap()
+            .insert_char('o', vec!["ē".parse().unwrap()]);
+        assert_eq!(p.first_letters("ok中"), "ehz");
+    }
+
+    #[test]
+    fn into_writer() {
+        /// Writer into a fixed buffer, fails when it is full.
+        struct Fixed {
+            buf: [u8; 7],
+            len: usize,
+        }
+        impl fmt::Write for Fixed {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                let end = self.len + s.len();
+                self.buf.get_mut(self.len..end).ok_or(fmt::Error)?;
+                self.buf[self.len..end].copy_from_slice(s.as_bytes());
+                self.len = end;
+                Ok(())
+            }
+        }
+
+        let p = Pinyinizer::from_data("U+4F60: nǐ\nU+597D: hǎo,hào\n").unwrap();
+        let mut w = Fixed {
+            buf: [0; 7],
+            len: 0,
+        };
+        p.first_letters_into("你好!", &mut w).unwrap();
+        assert_eq!(&w.buf[..w.len], b"nh!");
+        assert!(p
+            .pinyin_into("你好", ToneRepresentation::Numbered, &mut w)
+            .is_err());
+        // stops at the first error
+        assert_eq!(&w.buf[..w.len], b"nh!ni3 ");
+    }
+
+    #[test]
+    fn instances_are_independent() {
+        let a = Pinyinizer::from_data("U+4F60: nǐ\n").unwrap();
+        let b = Pinyinizer::from_data("U+4F60: nǐ\n").unwrap();
+        a.user_dict()
+            .write()
+            .unwrap()
+            .insert_char('你', vec!["nín".parse().unwrap()]);
+        assert_eq!(a.pinyin("你", ToneRepresentation::Unicode), "nín ");
+        assert_eq!(b.pinyin("你", ToneRepresentation::Unicode), "nǐ ");
+    }
+
+    #[test]
+    fn share_between_threads() {
+        let p = std::sync::Arc::new(Pinyinizer::from_data("U+4F60: nǐ\n").unwrap());
+        let p2 = p.clone();
+        let r = std::thread::spawn(move || p2.first_letters("你"))
+            .join()
+            .unwrap();
+        assert_eq!(r, "n");
+    }
+}
@@ -0,0 +1,117 @@
+//! Find runs of text that have no reading, such as ASCII in logs and code comments, so that
+//! they are copied to the output without decoding and looking up each char.
+
+/// First byte of the UTF-8 encoding of code point `low`. Bytes less than it only start or
+/// continue chars less than `low`, because UTF-8 keeps the order of code points.
+pub(crate) fn threshold(low: u32) -> u8 {
+    match char::from_u32(low) {
+        Some(c) => {
+            let mut buf = [0; 4];
+            c.encode_utf8(&mut buf);
+            buf[0]
+        }
+        // 0xF8 and above never appear in UTF-8
+        None if low > char::MAX as u32 => 0xF8,
+        None => 0,
+    }
+}
+
+/// Length of the longest prefix of `s` whose bytes are all less than `threshold`, by
+/// [`threshold`] the prefix ends at a char boundary.
+// kept out of line, inlining it into the lookup loop slows down text of Chinese only
+#[inline(never)]
+pub(crate) fn skip_below(s: &[u8], threshold: u8) -> usize {
+    let i = skip_chunks(s, threshold);
+    i + skip_scalar(&s[i..], threshold)
+}
+
+fn skip_scalar(s: &[u8], threshold: u8) -> usize {
+    s.iter().position(|b| *b >= threshold).unwrap_or(s.len())
+}
+
+/// Skip 16 bytes a time, stop at the first byte not less than `threshold` or the last
+/// partial chunk.
+#[cfg(target_arch = "x86_64")]
+fn skip_chunks(s: &[u8], threshold: u8) -> usize {
+    use core::arch::x86_64::*;
+
+    let mut i = 0;
+    // SAFETY: SSE2 is always available on x86_64, loads are unaligned and in bounds.
+    unsafe {
+        let t = _mm_set1_epi8(threshold as i8);
+        while i + 16 <= s.len() {
+            let v = _mm_loadu_si128(s.as_ptr().add(i) as *const __m128i);
+            // unsigned `v >= t`
+            let ge = _mm_cmpeq_epi8(_mm_max_epu8(v, t), v);
+            let mask = _mm_movemask_epi8(ge);
+            if mask != 0 {
+                return i + mask.trailing_zeros() as usize;
+            }
+            i += 16;
+        }
+    }
+    i
+}
+
+#[cfg(target_arch = "aarch64")]
+fn skip_chunks(s: &[u8], threshold: u8) -> usize {
+    use core::arch::aarch64::*;
+
+    let mut i = 0;
+    // SAFETY: NEON is always available on aarch64, loads are in bounds.
+    unsafe {
+        let t = vdupq_n_u8(threshold);
+        while i + 16 <= s.len() {
+            let v = vld1q_u8(s.as_ptr().add(i));
+            if vmaxvq_u8(vcgeq_u8(v, t)) != 0 {
+                // the scalar scan finds the position in the chunk
+                break;
+            }
+            i += 16;
+        }
+    }
+    i
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn skip_chunks(_s: &[u8], _threshold: u8) -> usize {
+    0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, 0)]
+    #[case(0x41, 0x41)]
+    #[case(0x3000, 0xE3)]
+    #[case(0x20000, 0xF0)]
+    #[case(u32::MAX, 0xF8)]
+    fn test_threshold(#[case] low: u32, #[case] exp: u8) {
+        assert_eq!(threshold(low), exp);
+    }
+
+    #[rstest]
+    #[case("", 0x3000, 0)]
+    #[case("abc", 0, 0)]
+    #[case("abc", 0x3000, 3)]
+    #[case("hello, wörld ©2023 中国", 0x3000, "hello, wörld ©2023 ".len())]
+    #[case("hello, wörld", 0x80, "hello, w".len())]
+    #[case("a中", u32::MAX, "a中".len())]
+    fn skip(#[case] s: &str, #[case] low: u32, #[case] exp: usize) {
+        assert_eq!(skip_below(s.as_bytes(), threshold(low)), exp);
+    }
+
+    #[test]
+    fn same_as_scalar() {
+        let text = "The quick brown fox jumps over the lazy dog. ".repeat(3);
+        for pos in 0..=text.len() {
+            let s = format!("{}中{}", &text[..pos], &text[pos..]);
+            let t = threshold(0x3000);
+            assert_eq!(skip_below(s.as_bytes(), t), pos);
+            assert_eq!(skip_scalar(s.as_bytes(), t), pos);
+        }
+    }
+}
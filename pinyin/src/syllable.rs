@@ -0,0 +1,243 @@
+//! Spelling of pinyin syllables. Also used by the build script to encode the embedded pinyin
+//! data, so it must not depend on other modules of the crate.
+use alloc::string::String;
+
+/// Spelling of initials, in the order of `Initials` enum.
+#[rustfmt::skip]
+pub(crate) const INITIALS: [&str; 24] = [
+    "", "b", "p", "m", "f", "d", "t", "n", "l", "g", "k", "h", "j", "q", "x",
+    "zh", "ch", "sh", "r", "z", "c", "s", "y", "w",
+];
+
+/// Spelling of finals, in the order of `Finals` enum.
+#[rustfmt::skip]
+pub(crate) const FINALS: [&str; 41] = [
+    "", "a", "o", "e", "u", "i", "ai", "ao", "ei", "ou", "ong", "an", "en", "ang", "eng", "er",
+    "ia", "ie", "iao", "ian", "ing", "iang", "in", "iou", "iong", "iu", "ui", "ua", "uai", "un",
+    "uan", "uang", "uo", "ue", "ü", "üe", "üan", "m", "n", "ng", "ê",
+];
+
+/// Combining tone marks, used by upstream data for letters without precomposed forms, such
+/// as `m̄` and `ê̌`.
+const COMBINING_TONE_MARKS: [(char, u8); 4] = [
+    ('\u{304}', 1),
+    ('\u{301}', 2),
+    ('\u{30c}', 3),
+    ('\u{300}', 4),
+];
+
+/// Letters with tone marks, and their base letter and tone.
+#[rustfmt::skip]
+const TONE_MARKS: [(char, char, u8); 30] = [
+    ('ā', 'a', 1), ('á', 'a', 2), ('ǎ', 'a', 3), ('à', 'a', 4),
+    ('ē', 'e', 1), ('é', 'e', 2), ('ě', 'e', 3), ('è', 'e', 4),
+    ('ī', 'i', 1), ('í', 'i', 2), ('ǐ', 'i', 3), ('ì', 'i', 4),
+    ('ō', 'o', 1), ('ó', 'o', 2), ('ǒ', 'o', 3), ('ò', 'o', 4),
+    ('ū', 'u', 1), ('ú', 'u', 2), ('ǔ', 'u', 3), ('ù', 'u', 4),
+    ('ǖ', 'ü', 1), ('ǘ', 'ü', 2), ('ǚ', 'ü', 3), ('ǜ', 'ü', 4),
+    ('ń', 'n', 2), ('ň', 'n', 3), ('ǹ', 'n', 4),
+    ('ḿ', 'm', 2), ('ế', 'ê', 2), ('ề', 'ê', 4),
+];
+
+/// Whether `c` may appear in a pinyin syllable.
+pub(crate) fn is_pinyin_char(c: char) -> bool {
+    c.is_ascii_alphabetic()
+        || matches!(c, 'ü' | 'ê')
+        || TONE_MARKS.iter().any(|(marked, _, _)| *marked == c)
+        || COMBINING_TONE_MARKS.iter().any(|(mark, _)| *mark == c)
+}
+
+/// Remove the tone mark of a syllable, such as `zhōng` to (`zhong`, 1). Tone is 0 if the
+/// syllable has no tone mark.
+pub(crate) fn split_tone(s: &str) -> (String, u8) {
+    let mut tone = 0;
+    let base = s
+        .chars()
+        .filter_map(|c| {
+            if let Some((_, t)) = COMBINING_TONE_MARKS.iter().find(|(mark, _)| *mark == c) {
+                tone = *t;
+                return None;
+            }
+            match TONE_MARKS.iter().find(|(marked, _, _)| *marked == c) {
+                Some((_, base, t)) => {
+                    tone = *t;
+                    Some(*base)
+                }
+                None => Some(c),
+            }
+        })
+        .collect();
+    (base, tone)
+}
+
+/// Split a syllable without tone mark to indexes of its initial and final, such as `zhong`
+/// to indexes of `zh` and `ong`.
+pub(crate) fn split_syllable(s: &str) -> Option<(u8, u8)> {
+    let final_index = |f: &str| {
+        FINALS[1..]
+            .iter()
+            .position(|x| *x == f)
+            .map(|i| i as u8 + 1)
+    };
+
+    // two letters initials come first, so that `zh` is not taken as `z`
+    let initial = (1..INITIALS.len())
+        .filter(|&i| s.starts_with(INITIALS[i]))
+        .max_by_key(|&i| INITIALS[i].len());
+    initial
+        .and_then(|i| final_index(&s[INITIALS[i].len()..]).map(|f| (i as u8, f)))
+        // syllables without initial, including syllabic nasals `m`, `n` and `ng`
+        .or_else(|| final_index(s).map(|f| (0, f)))
+}
+
+/// Initials that each final combines with in the syllable table of standard Mandarin, `-`
+/// for syllables without initial.
+#[cfg(feature = "std")]
+#[rustfmt::skip]
+const STANDARD_SYLLABLES: [(&str, &str); 40] = [
+    ("a", "- b p m f d t n l g k h zh ch sh z c s y w"),
+    ("o", "- b p m f l y w"),
+    ("e", "- m d t n l g k h zh ch sh r z c s y"),
+    ("u", "b p m f d t n l g k h j q x zh ch sh r z c s y w"),
+    ("i", "b p m d t n l j q x zh ch sh r z c s y"),
+    ("ai", "- b p m d t n l g k h zh ch sh z c s w"),
+    ("ao", "- b p m d t n l g k h zh ch sh r z c s y"),
+    ("ei", "- b p m f d t n l g k h zh sh z w"),
+    ("ou", "- p m f d t n l g k h zh ch sh r z c s y"),
+    ("ong", "d t n l g k h zh ch r z c s y"),
+    ("an", "- b p m f d t n l g k h zh ch sh r z c s y w"),
+    ("en", "- b p m f d n g k h zh ch sh r z c s w"),
+    ("ang", "- b p m f d t n l g k h zh ch sh r z c s y w"),
+    ("eng", "- b p m f d t n l g k h zh ch sh r z c s w"),
+    ("er", "-"),
+    ("ia", "d l j q x"),
+    ("ie", "b p m d t n l j q x"),
+    ("iao", "b p m d t n l j q x"),
+    ("ian", "b p m d t n l j q x"),
+    ("ing", "b p m d t n l j q x y"),
+    ("iang", "n l j q x"),
+    ("in", "b p m n l j q x y"),
+    ("iou", ""),
+    ("iong", "j q x"),
+    ("iu", "m d n l j q x"),
+    ("ui", "d t g k h zh ch sh r z c s"),
+    ("ua", "g k h zh ch sh r"),
+    ("uai", "g k h zh ch sh"),
+    ("un", "d t l g k h j q x zh ch sh r z c s y"),
+    ("uan", "d t n l g k h j q x zh ch sh r z c s y"),
+    ("uang", "g k h zh ch sh"),
+    ("uo", "d t n l g k h zh ch sh r z c s"),
+    ("ue", "j q x y"),
+    ("ü", "n l"),
+    ("üe", "n l"),
+    ("üan", ""),
+    ("m", "- h"),
+    ("n", "-"),
+    ("ng", "- h"),
+    ("ê", "-"),
+];
+
+/// Whether a syllable with or without tone mark is in the syllable table of standard
+/// Mandarin. Data loads any initial with any final, such as `fiào` of `覅` from Wu.
+#[cfg(feature = "std")]
+pub(crate) fn is_standard(s: &str) -> bool {
+    let (base, _) = split_tone(s);
+    let Some((initial, final_)) = split_syllable(&base) else {
+        return false;
+    };
+    let initial = match INITIALS[initial as usize] {
+        "" => "-",
+        i => i,
+    };
+    STANDARD_SYLLABLES
+        .iter()
+        .find(|(f, _)| *f == FINALS[final_ as usize])
+        .is_some_and(|(_, initials)| initials.split(' ').any(|i| i == initial))
+}
+
+/// Encode to the `u16` representation of `Pinyin`.
+pub(crate) const fn encode(initial: u8, final_: u8, tone: u8) -> u16 {
+    // same as `Pinyin` bitfield layout: tones in bits 0..3 and initials in bits 3..8 of the
+    // first byte, finals in the second byte, converted to u16 in big endian.
+    (((initial << 3) | tone) as u16) << 8 | final_ as u16
+}
+
+/// Parse a syllable with tone mark, such as `zhōng`, to the `u16` representation of `Pinyin`.
+pub(crate) fn parse(s: &str) -> Option<u16> {
+    let (base, tone) = split_tone(s);
+    split_syllable(&base).map(|(initial, final_)| encode(initial, final_, tone))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pinyin::{py, Finals, Initials, Tones};
+    use crate::Pinyin;
+    use rstest::rstest;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn same_order_as_enums() {
+        let initials: Vec<_> = Initials::iter().map(|i| i.as_ref().to_string()).collect();
+        assert_eq!(initials, INITIALS);
+        // `Finals::None` is serialized as "none"
+        let finals: Vec<_> = Finals::iter().map(|f| f.as_ref().to_string()).collect();
+        assert_eq!(finals[1..], FINALS[1..]);
+    }
+
+    #[test]
+    fn parse_syllable() {
+        let parse = |s| Pinyin::from(parse(s).unwrap());
+        assert_eq!(parse("zhōng"), py(Initials::ZH, Finals::Ong, Tones::One));
+        assert_eq!(parse("lǜ"), py(Initials::L, Finals::V, Tones::Four));
+        assert_eq!(parse("ǹg"), py(Initials::None, Finals::NG, Tones::Four));
+        assert_eq!(parse("ń"), py(Initials::None, Finals::N, Tones::Two));
+        assert_eq!(parse("er"), py(Initials::None, Finals::ER, Tones::None));
+        assert_eq!(super::parse("xyz"), None);
+        assert_eq!(super::parse("zh"), None);
+    }
+
+    #[rstest]
+    #[case("ḿ", Initials::None, Finals::M, Tones::Two)]
+    #[case("m\u{300}", Initials::None, Finals::M, Tones::Four)]
+    #[case("hm", Initials::H, Finals::M, Tones::None)]
+    #[case("n\u{304}", Initials::None, Finals::N, Tones::One)]
+    #[case("ňg", Initials::None, Finals::NG, Tones::Three)]
+    #[case("hng", Initials::H, Finals::NG, Tones::None)]
+    #[case("ê\u{304}", Initials::None, Finals::EH, Tones::One)]
+    #[case("ế", Initials::None, Finals::EH, Tones::Two)]
+    #[case("ê\u{30c}", Initials::None, Finals::EH, Tones::Three)]
+    #[case("a\u{301}", Initials::None, Finals::A, Tones::Two)]
+    #[case("lüè", Initials::L, Finals::VE, Tones::Four)]
+    #[case("fiào", Initials::F, Finals::Iao, Tones::Four)]
+    fn unusual_syllables(
+        #[case] s: &str,
+        #[case] initials: Initials,
+        #[case] finals: Finals,
+        #[case] tones: Tones,
+    ) {
+        assert_eq!(Pinyin::from(parse(s).unwrap()), py(initials, finals, tones));
+    }
+
+    #[rstest]
+    #[cfg(feature = "std")]
+    #[case("zhōng", true)]
+    #[case("lǜ", true)]
+    #[case("nüè", true)]
+    #[case("yuán", true)]
+    #[case("wēng", true)]
+    #[case("ér", true)]
+    #[case("hm", true)]
+    #[case("ǹg", true)]
+    #[case("ê\u{304}", true)]
+    #[case("fiào", false)]
+    #[case("shiang", false)]
+    #[case("bü", false)]
+    #[case("jü", false)]
+    #[case("song", true)]
+    #[case("shong", false)]
+    #[case("xyz", false)]
+    fn standard_syllables(#[case] s: &str, #[case] exp: bool) {
+        assert_eq!(is_standard(s), exp);
+    }
+}
@@ -0,0 +1,191 @@
+//! Reading overrides registered at runtime, they take precedence over the built-in data.
+use crate::db::parser::parse_user_dict;
+use crate::db::ParseErrors;
+use crate::phrase::Phrases;
+use crate::{Pinyin, ReadingCountMismatch};
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum UserDictEntry {
+    Char(char, Vec<Pinyin>),
+    Phrase(String, Vec<Pinyin>),
+}
+
+/// User defined readings of chars and phrases.
+#[derive(Debug, Clone, Default)]
+pub struct UserDict {
+    chars: HashMap<char, Vec<Pinyin>>,
+    /// Lowest char of `chars`.
+    low: Option<char>,
+    phrases: Phrases,
+}
+
+impl UserDict {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set readings of a char, the first reading is used in conversion. Return previous
+    /// readings if exist.
+    pub fn insert_char(&mut self, c: char, readings: Vec<Pinyin>) -> Option<Vec<Pinyin>> {
+        self.low = Some(self.low.map_or(c, |low| low.min(c)));
+        self.chars.insert(c, readings)
+    }
+
+    /// Set readings of a phrase, one reading for each char. Return previous readings if exist.
+    pub fn insert_phrase(
+        &mut self,
+        phrase: &str,
+        readings: Vec<Pinyin>,
+    ) -> Result<Option<Vec<Pinyin>>, ReadingCountMismatch> {
+        self.phrases.insert(phrase, readings)
+    }
+
+    pub fn remove_char(&mut self, c: char) -> Option<Vec<Pinyin>> {
+        let r = self.chars.remove(&c);
+        if self.low == Some(c) {
+            self.low = self.chars.keys().min().copied();
+        }
+        r
+    }
+
+    pub fn remove_phrase(&mut self, phrase: &str) -> Option<Vec<Pinyin>> {
+        self.phrases.remove(phrase)
+    }
+
+    pub fn get_char(&self, c: char) -> Option<&[Pinyin]> {
+        self.chars.get(&c).map(Vec::as_slice)
+    }
+
+    pub fn get_phrase(&self, phrase: &str) -> Option<&[Pinyin]> {
+        self.phrases.get(phrase)
+    }
+
+    /// Iterate over char overrides, in arbitrary order.
+    pub fn chars(&self) -> impl Iterator<Item = (char, &[Pinyin])> {
+        self.chars.iter().map(|(c, r)| (*c, r.as_slice()))
+    }
+
+    /// Iterate over phrase overrides, in the order of their text.
+    pub fn phrases(&self) -> impl Iterator<Item = (&str, &[Pinyin])> {
+        self.phrases.iter()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chars.is_empty() && self.phrases.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.chars.clear();
+        self.low = None;
+        self.phrases.clear();
+    }
+
+    /// Add overrides from text. Chars are in the same syntax as `pinyin.txt`, such as
+    /// `U+4E2D: zhōng,zhòng`, phrases separate readings by space, such as `中国: zhōng guó`.
+    /// Nothing is added if any line fails to parse.
+    pub fn load(&mut self, s: &str) -> Result<(), ParseErrors> {
+        for entry in parse_user_dict(s)? {
+            match entry {
+                UserDictEntry::Char(c, readings) => {
+                    self.insert_char(c, readings);
+                }
+                UserDictEntry::Phrase(phrase, readings) => {
+                    self.insert_phrase(&phrase, readings).unwrap();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Add overrides from a file, see [`UserDict::load`] for the syntax.
+    pub fn load_file(&mut self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let s = std::fs::read_to_string(path)?;
+        self.load(&s)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+
+    /// Lowest code point that starts a char or phrase, `u32::MAX` if empty.
+    pub(crate) fn lowest_char(&self) -> u32 {
+        match (self.low, self.phrases.first_char()) {
+            (Some(a), Some(b)) => a.min(b) as u32,
+            (Some(c), None) | (None, Some(c)) => c as u32,
+            (None, None) => u32::MAX,
+        }
+    }
+
+    /// Find the longest phrase at the start of `s`, return the phrase length in bytes and
+    /// its readings.
+    pub(crate) fn match_phrase(&self, s: &str) -> Option<(usize, &[Pinyin])> {
+        self.phrases.match_prefix(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn readings(s: &str) -> Vec<Pinyin> {
+        s.split(' ').map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn insert_remove() {
+        let mut dict = UserDict::new();
+        assert!(dict.is_empty());
+        assert_eq!(dict.insert_char('中', readings("zhòng")), None);
+        assert_eq!(dict.get_char('中'), Some(readings("zhòng").as_slice()));
+        assert_eq!(
+            dict.insert_phrase("银行", readings("yín háng")).unwrap(),
+            None
+        );
+        assert!(dict.insert_phrase("银行", readings("yín")).is_err());
+        assert_eq!(dict.chars().count(), 1);
+        assert_eq!(dict.phrases().count(), 1);
+
+        assert_eq!(dict.remove_char('中'), Some(readings("zhòng")));
+        assert_eq!(dict.remove_phrase("银行"), Some(readings("yín háng")));
+        assert!(dict.is_empty());
+    }
+
+    #[test]
+    fn test_match_phrase() {
+        let mut dict = UserDict::new();
+        dict.insert_phrase("银行", readings("yín háng")).unwrap();
+        dict.insert_phrase("银行家", readings("yín háng jiā"))
+            .unwrap();
+        assert_eq!(
+            dict.match_phrase("银行家们"),
+            Some(("银行家".len(), readings("yín háng jiā").as_slice()))
+        );
+        assert_eq!(
+            dict.match_phrase("银行"),
+            Some(("银行".len(), readings("yín háng").as_slice()))
+        );
+        assert_eq!(dict.match_phrase("银"), None);
+        assert_eq!(dict.match_phrase(""), None);
+    }
+
+    #[test]
+    fn test_load() {
+        let mut dict = UserDict::new();
+        dict.load("U+4E2D: zhòng  # 中\n银行: yín háng").unwrap();
+        assert_eq!(dict.get_char('中'), Some(readings("zhòng").as_slice()));
+        assert_eq!(
+            dict.get_phrase("银行"),
+            Some(readings("yín háng").as_slice())
+        );
+        assert!(dict.load("银行: yín").is_err());
+        assert!(dict.load("U+4E2D zhòng").is_err());
+
+        // lines end with CRLF, errors are reported with line numbers and nothing is added
+        dict.load("U+4E2E: jiū\r\n银行家: yín háng jiā\r\n")
+            .unwrap();
+        assert_eq!(dict.get_char('丮'), Some(readings("jiū").as_slice()));
+        let errors = dict.load("U+4E2F: gè\n\nU+4E30 fēng\n").unwrap_err().0;
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line, 3);
+        assert_eq!(dict.get_char('丯'), None);
+    }
+}
@@ -0,0 +1,43 @@
+//! Conversions into a writer do not allocate. In its own test binary, as the counting
+//! allocator replaces the allocator of the whole binary.
+use piny::db::DB;
+use piny::{Pinyinizer, ToneRepresentation};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// Allocator that counts allocations of each thread, so that tests running in parallel are
+/// not counted.
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|n| n.set(n.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAlloc = CountingAlloc;
+
+#[test]
+fn into_writer_without_allocation() {
+    let mut db = DB::load("U+4F60: nǐ\nU+597D: hǎo\n").unwrap();
+    db.load_phrases("好好: hǎo hāo\n你好好你: nǐ hǎo hāo nǐ\n")
+        .unwrap();
+    let p = Pinyinizer::new(db);
+    let mut out = String::with_capacity(64);
+    let before = ALLOCATIONS.with(Cell::get);
+    p.pinyin_into("你好好吗, 好", ToneRepresentation::Unicode, &mut out)
+        .unwrap();
+    p.first_letters_into("你好好吗", &mut out).unwrap();
+    assert_eq!(ALLOCATIONS.with(Cell::get), before);
+    assert_eq!(out, "nǐ hǎo hāo 吗 ,  hǎo nhh吗");
+}
@@ -0,0 +1,76 @@
+use piny::db::{self, ExportFormat, Lint, LintKind, DB};
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage:
+  pinyin lint FILE [--diff OTHER]    check a pinyin data file, optionally list
+                                     readings that differ from OTHER
+  pinyin export json|csv|text FILE   write chars and readings of a pinyin data
+                                     file to stdout";
+
+fn read(path: &str) -> Result<String, String> {
+    std::fs::read_to_string(path).map_err(|e| format!("failed to read {}: {}", path, e))
+}
+
+fn is_error(l: &Lint) -> bool {
+    matches!(l.kind, LintKind::Error(_))
+}
+
+/// Print problems of `path` and differences from `other`, return whether `path` has no
+/// problem.
+fn lint(path: &str, other: Option<&str>) -> Result<bool, String> {
+    let data = read(path)?;
+    let lints = db::lint(&data);
+    for l in &lints {
+        println!("{}: {}", path, l);
+    }
+
+    if let Some(other) = other {
+        let other = DB::load(&read(other)?).map_err(|e| format!("{}:\n{}", other, e))?;
+        // readings of bad lines are skipped, their problems are reported above
+        let db = DB::load(&data).unwrap_or_else(|_| {
+            let good: Vec<&str> = data
+                .lines()
+                .enumerate()
+                .filter(|(idx, _)| !lints.iter().any(|l| l.line == idx + 1 && is_error(l)))
+                .map(|(_, line)| line)
+                .collect();
+            DB::load(&good.join("\n")).unwrap_or_default()
+        });
+        for d in db::diff(&other, &db) {
+            println!("{}", d);
+        }
+    }
+    Ok(lints.is_empty())
+}
+
+fn export(format: ExportFormat, path: &str) -> Result<bool, String> {
+    let db = DB::load(&read(path)?).map_err(|e| format!("{}:\n{}", path, e))?;
+    db.export(format, std::io::stdout().lock())
+        .map_err(|e| format!("failed to export: {}", e))?;
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let r = match args.as_slice() {
+        ["lint", file] => lint(file, None),
+        ["lint", file, "--diff", other] => lint(file, Some(other)),
+        ["export", "json", file] => export(ExportFormat::Json, file),
+        ["export", "csv", file] => export(ExportFormat::Csv, file),
+        ["export", "text", file] => export(ExportFormat::Text, file),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match r {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+    }
+}
@@ -0,0 +1,137 @@
+//! Convert constant Chinese text to pinyin at compile time, the macros expand to string
+//! literals, such as menu keys and slugs that need `&'static str`.
+//!
+//! The conversion is the same as [`piny::pinyin`] and [`piny::first_letters`] with the
+//! embedded pinyin data. A Han character without reading fails the compilation, other chars
+//! are kept as is.
+//!
+//! ```
+//! use piny_macros::{first_letters, pinyin};
+//!
+//! const KEY: &str = pinyin!("中国", numbered);
+//! assert_eq!(KEY, "zhong1 guo2 ");
+//! assert_eq!(first_letters!("中国"), "zg");
+//! ```
+//!
+//! ```compile_fail
+//! // `㐂` has no reading in the data
+//! const KEY: &str = piny_macros::pinyin!("中㐂");
+//! ```
+use piny::ToneRepresentation;
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Token};
+
+/// Arguments of `pinyin!`: the text and an optional tone representation.
+struct PinyinArgs {
+    text: LitStr,
+    tone_repr: ToneRepresentation,
+}
+
+impl Parse for PinyinArgs {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let text = input.parse()?;
+        let mut tone_repr = ToneRepresentation::Unicode;
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            let ident: Ident = input.parse()?;
+            tone_repr = match ident.to_string().as_str() {
+                "unicode" => ToneRepresentation::Unicode,
+                "numbered" => ToneRepresentation::Numbered,
+                "none" => ToneRepresentation::None,
+                _ => {
+                    return Err(syn::Error::new(
+                        ident.span(),
+                        "expected `unicode`, `numbered` or `none`",
+                    ))
+                }
+            };
+            input.parse::<Option<Token![,]>>()?;
+        }
+        Ok(Self { text, tone_repr })
+    }
+}
+
+/// Return an error at `span` for the first Han character of `s` without reading.
+fn check(s: &str, span: Span) -> syn::Result<()> {
+    let pinyinizer = piny::default_pinyinizer();
+    match s
+        .chars()
+        .find(|&c| piny::db::is_han(c) && pinyinizer.db().get(c).is_none())
+    {
+        Some(c) => Err(syn::Error::new(
+            span,
+            format!("'{}' (U+{:04X}) has no pinyin reading", c, c as u32),
+        )),
+        None => Ok(()),
+    }
+}
+
+fn expand_pinyin(input: TokenStream2) -> syn::Result<TokenStream2> {
+    let args: PinyinArgs = syn::parse2(input)?;
+    let text = args.text.value();
+    check(&text, args.text.span())?;
+    let s = piny::pinyin(&text, args.tone_repr);
+    Ok(LitStr::new(&s, args.text.span()).into_token_stream())
+}
+
+fn expand_first_letters(input: TokenStream2) -> syn::Result<TokenStream2> {
+    let text: LitStr = syn::parse2(input)?;
+    check(&text.value(), text.span())?;
+    let s = piny::first_letters(&text.value());
+    Ok(LitStr::new(&s, text.span()).into_token_stream())
+}
+
+fn to_tokens(r: syn::Result<TokenStream2>) -> TokenStream {
+    r.unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Pinyin of a string literal separated by space, same as [`piny::pinyin`]. Tones are
+/// unicode marks by default, the optional second argument is one of `unicode`, `numbered`
+/// and `none`, such as `pinyin!("中国", numbered)` for `"zhong1 guo2 "`.
+#[proc_macro]
+pub fn pinyin(input: TokenStream) -> TokenStream {
+    to_tokens(expand_pinyin(input.into()))
+}
+
+/// First letters of a string literal, same as [`piny::first_letters`], such as
+/// `first_letters!("中国")` for `"zg"`.
+#[proc_macro]
+pub fn first_letters(input: TokenStream) -> TokenStream {
+    to_tokens(expand_first_letters(input.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use rstest::rstest;
+
+    fn expanded(r: syn::Result<TokenStream2>) -> String {
+        syn::parse2::<LitStr>(r.unwrap()).unwrap().value()
+    }
+
+    #[rstest]
+    #[case(quote!("中国"), "zhōng guó ")]
+    #[case(quote!("中国", unicode), "zhōng guó ")]
+    #[case(quote!("中国", numbered), "zhong1 guo2 ")]
+    #[case(quote!("中国", none,), "zhong guo ")]
+    #[case(quote!("A中"), "A zhōng ")]
+    fn pinyin(#[case] input: TokenStream2, #[case] exp: &str) {
+        assert_eq!(expanded(expand_pinyin(input)), exp);
+    }
+
+    #[test]
+    fn first_letters() {
+        assert_eq!(expanded(expand_first_letters(quote!("中国 A"))), "zg A");
+    }
+
+    #[rstest]
+    #[case(quote!("中㐂"), "'㐂' (U+3402) has no pinyin reading")]
+    #[case(quote!("中国", numeric), "expected `unicode`, `numbered` or `none`")]
+    #[case(quote!(中国), "expected string literal")]
+    fn errors(#[case] input: TokenStream2, #[case] exp: &str) {
+        assert_eq!(expand_pinyin(input).unwrap_err().to_string(), exp);
+    }
+}
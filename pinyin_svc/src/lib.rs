@@ -1,5 +1,5 @@
-use std::str::FromStr;
 use core::str::Utf8Error;
+use std::str::FromStr;
 
 /// Warp `param()` function not decode url encoded string.
 /// Use this type to decode raw url encoded string to a `String`.
@@ -11,8 +11,7 @@ impl FromStr for UrlEncodedString {
     type Err = Utf8Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = percent_encoding::percent_decode(s.as_bytes())
-            .decode_utf8()?;
+        let s = percent_encoding::percent_decode(s.as_bytes()).decode_utf8()?;
         Ok(Self(s.to_string()))
     }
 }
@@ -15,6 +15,13 @@ use warp::{Filter, Reply};
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     env_logger::init();
+    if let Ok(path) = std::env::var("PINYIN_USER_DICT") {
+        piny::user_dict()
+            .write()
+            .unwrap()
+            .load_file(&path)
+            .unwrap_or_else(|e| panic!("failed to load user dictionary {}: {}", path, e));
+    }
 
     let pinyin = warp::path!("pinyin" / UrlEncodedString)
         .and(warp::get())
@@ -58,6 +65,8 @@ async fn main() {
 pub struct PinYinQuery {
     #[serde(alias = "t", default)]
     tone_repr: ToneRepresentation,
+    #[serde(alias = "n", default)]
+    read_numbers: bool,
 }
 
 /// Return pinyin of a Chinese characters separated by space.
@@ -69,10 +78,14 @@ utoipa::path(
     params(
         ("s"=String, Path, description="String to convert"),
         ("t"=inline(Option<ToneRepresentation>), Query, description="How to represent the tone of a pinyin syllable."),
+        ("n"=Option<bool>, Query, description="Read Arabic numerals as Chinese numbers."),
     )
 ))]
 fn pinyin_handler(s: UrlEncodedString, q: PinYinQuery) -> impl Reply {
-    let s: String = s.into();
+    let mut s: String = s.into();
+    if q.read_numbers {
+        s = piny::read_numbers(&s);
+    }
     piny::pinyin(&s, q.tone_repr)
 }
 